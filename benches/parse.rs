@@ -0,0 +1,176 @@
+//! Benchmarks of transaction parsing throughput.
+
+#[macro_use]
+extern crate criterion;
+extern crate btc_parse_tutor;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use btc_parse_tutor::{Block, BlockHeader, Hash256, Limits, SizeHint, Transaction, TransactionRef, hash256};
+use criterion::{Criterion, Throughput};
+
+/// Transaction from block 170, the first one spending coins.
+const LEGACY: &str = "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000";
+
+/// SegWit transaction from BIP143 test vectors.
+const SEGWIT: &str = "01000000000102fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f00000000494830450221008b9d1dc26ba6a9cb62127b02742fa9d754cd3bebf337f7a55d114c8e5cdd30be022040529b194ba3f9281a99f2b1c0a19c0489bc22ede944ccf4ecbab4cc618ef3ed01eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac000247304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a0220573a954c4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee0121025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeee635711000000";
+
+/// Number of inputs of the large transaction.
+const LARGE_INPUT_COUNT: usize = 1000;
+
+/// Number of transactions of the large block.
+const LARGE_BLOCK_TX_COUNT: usize = 10_000;
+
+/// Number of bytes currently allocated.
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+/// The highest value of `ALLOCATED` since it was last reset.
+static PEAK_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+/// Allocator tracking the peak of allocated memory.
+struct PeakAllocator;
+
+unsafe impl GlobalAlloc for PeakAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK_ALLOCATED.fetch_max(allocated, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAllocator = PeakAllocator;
+
+/// Returns the peak number of bytes `f` allocated on top of those allocated already.
+fn peak_allocation<T, F: FnOnce() -> T>(f: F) -> usize {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    PEAK_ALLOCATED.store(before, Ordering::Relaxed);
+    criterion::black_box(f());
+    PEAK_ALLOCATED.load(Ordering::Relaxed) - before
+}
+
+/// Returns transaction with `LARGE_INPUT_COUNT` inputs.
+fn large_transaction() -> Transaction {
+    let mut transaction = Transaction::from_hex(LEGACY).expect("valid transaction");
+    transaction.inputs = vec![transaction.inputs[0].clone(); LARGE_INPUT_COUNT];
+    transaction
+}
+
+/// Returns the serialized benchmarked transactions along with their names.
+///
+/// The transactions are decoded upfront so that only parsing is measured.
+fn transactions() -> Vec<(&'static str, Vec<u8>)> {
+    let legacy = Transaction::from_hex(LEGACY).expect("valid transaction");
+    let segwit = Transaction::from_hex(SEGWIT).expect("valid transaction");
+
+    let large = large_transaction();
+
+    [("legacy", legacy), ("large", large), ("segwit", segwit)]
+        .iter()
+        .map(|&(name, ref transaction)| {
+            let mut bytes = Vec::new();
+            transaction.serialize(&mut bytes).expect("vec doesn't fail");
+            (name, bytes)
+        })
+        .collect()
+}
+
+fn bench_parse(c: &mut Criterion, throughput: fn(&[u8]) -> Throughput, group_name: &str) {
+    let mut group = c.benchmark_group(group_name);
+    for (name, bytes) in transactions() {
+        group.throughput(throughput(&bytes));
+        group.bench_function(format!("{}/owned", name), |b| b.iter(|| {
+            Transaction::deserialize(&mut criterion::black_box(&*bytes)).expect("valid transaction")
+        }));
+        group.bench_function(format!("{}/borrowed", name), |b| b.iter(|| {
+            TransactionRef::deserialize(&mut criterion::black_box(&*bytes)).expect("valid transaction")
+        }));
+    }
+    group.finish();
+}
+
+/// Returns serialized block containing `LARGE_BLOCK_TX_COUNT` legacy transactions.
+fn large_block() -> Vec<u8> {
+    let transaction = Transaction::from_hex(LEGACY).expect("valid transaction");
+    let block = Block {
+        header: BlockHeader {
+            version: 1,
+            prev_block: Hash256([0; 32]),
+            merkle_root: Hash256([0; 32]),
+            time: 0,
+            bits: 0,
+            nonce: 0,
+        },
+        transactions: vec![transaction; LARGE_BLOCK_TX_COUNT],
+    };
+
+    let mut bytes = Vec::new();
+    block.serialize(&mut bytes).expect("vec doesn't fail");
+    bytes
+}
+
+/// Compares parsing of a large block with and without size hint.
+fn block_with_hint(c: &mut Criterion) {
+    let bytes = large_block();
+    let hint = SizeHint {
+        transactions: LARGE_BLOCK_TX_COUNT as u64,
+        inputs: 1,
+        outputs: 2,
+    };
+
+    let mut group = c.benchmark_group("block");
+    group.throughput(Throughput::Elements(LARGE_BLOCK_TX_COUNT as u64));
+    group.bench_function("without_hint", |b| b.iter(|| {
+        Block::deserialize(&mut criterion::black_box(&*bytes)).expect("valid block")
+    }));
+    group.bench_function("with_hint", |b| b.iter(|| {
+        Block::deserialize_with_hint(&mut criterion::black_box(&*bytes), &Limits::consensus(), &hint).expect("valid block")
+    }));
+    group.finish();
+}
+
+/// Serializes the transaction into a vector and hashes it, as `txid` would without streaming.
+fn txid_serialized(transaction: &Transaction) -> Hash256 {
+    let mut bytes = Vec::new();
+    transaction.serialize_legacy(&mut bytes).expect("vec doesn't fail");
+    hash256(&bytes)
+}
+
+/// Compares computing txid of a large transaction by streaming into the hasher and via serialized bytes.
+///
+/// Criterion can't measure memory, so the peak allocation of each is printed before timing.
+fn txid(c: &mut Criterion) {
+    let transaction = large_transaction();
+    println!(
+        "txid peak allocation of {} input transaction: streaming {} B, serialized {} B",
+        LARGE_INPUT_COUNT,
+        peak_allocation(|| transaction.txid()),
+        peak_allocation(|| txid_serialized(&transaction)),
+    );
+
+    let mut group = c.benchmark_group("txid");
+    group.bench_function("streaming", |b| b.iter(|| criterion::black_box(&transaction).txid()));
+    group.bench_function("serialized", |b| b.iter(|| txid_serialized(criterion::black_box(&transaction))));
+    group.finish();
+}
+
+/// Reports transactions per second.
+fn transactions_per_second(c: &mut Criterion) {
+    bench_parse(c, |_| Throughput::Elements(1), "transactions");
+}
+
+/// Reports bytes per second.
+fn bytes_per_second(c: &mut Criterion) {
+    bench_parse(c, |bytes| Throughput::Bytes(bytes.len() as u64), "bytes");
+}
+
+criterion_group!(benches, transactions_per_second, bytes_per_second, block_with_hint, txid);
+criterion_main!(benches);
@@ -0,0 +1,123 @@
+//! Rendering of output scripts as human-readable addresses.
+
+use bech32::{hrp, segwit, Hrp};
+use crate::prelude::*;
+use crate::script::{Script, ScriptType};
+
+/// Bitcoin network the address is intended for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+}
+
+impl Network {
+    /// Magic bytes identifying the network in messages and block files, in little endian.
+    pub fn magic(self) -> u32 {
+        match self {
+            Network::Mainnet => 0xd9b4_bef9,
+            Network::Testnet => 0x0709_110b,
+        }
+    }
+
+    /// Returns the network identified by the magic, if it's known.
+    pub fn from_magic(magic: u32) -> Option<Self> {
+        match magic {
+            0xd9b4_bef9 => Some(Network::Mainnet),
+            0x0709_110b => Some(Network::Testnet),
+            _ => None,
+        }
+    }
+
+    /// Version byte of base58check encoded P2PKH address.
+    fn p2pkh_version(self) -> u8 {
+        match self {
+            Network::Mainnet => 0x00,
+            Network::Testnet => 0x6f,
+        }
+    }
+
+    /// Version byte of base58check encoded P2SH address.
+    fn p2sh_version(self) -> u8 {
+        match self {
+            Network::Mainnet => 0x05,
+            Network::Testnet => 0xc4,
+        }
+    }
+
+    /// Human-readable part of bech32 encoded address. (BIP173)
+    fn hrp(self) -> Hrp {
+        match self {
+            Network::Mainnet => hrp::BC,
+            Network::Testnet => hrp::TB,
+        }
+    }
+}
+
+/// Encodes the hash prefixed with version byte as base58check.
+fn base58check(version: u8, hash: &[u8]) -> String {
+    let mut data = Vec::with_capacity(1 + hash.len());
+    data.push(version);
+    data.extend_from_slice(hash);
+    bs58::encode(data).with_check().into_string()
+}
+
+impl Script {
+    /// Renders the address of the script if it matches one of the standard templates.
+    ///
+    /// Returns `None` for scripts that don't have an address.
+    pub fn address(&self, network: Network) -> Option<String> {
+        match self.script_type() {
+            ScriptType::P2pkh => Some(base58check(network.p2pkh_version(), &self.0[3..23])),
+            ScriptType::P2sh => Some(base58check(network.p2sh_version(), &self.0[2..22])),
+            ScriptType::P2wpkh | ScriptType::P2wsh => {
+                let (_, program) = self.witness_program()?;
+                segwit::encode(network.hrp(), segwit::VERSION_0, program).ok()
+            },
+            ScriptType::P2pk | ScriptType::OpReturn | ScriptType::NonStandard => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex;
+
+    fn address(script_hex: &str, network: Network) -> Option<String> {
+        Script(hex::decode(script_hex).unwrap()).address(network)
+    }
+
+    #[test]
+    fn standard_scripts() {
+        let cases = [
+            // P2PKH of the genesis block key
+            ("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac", "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa", "mpXwg4jMtRhuSpVq4xS3HFHmCmWp9NyGKt"),
+            ("a914748284390f9e263a4b766a75d0633c50426eb87587", "3CK4fEwbMP7heJarmU4eqA3sMbVJyEnU3V", "2N3sGiyscxqd3r6DQSbgXT738ZwhUpBqkej"),
+            // P2WPKH and P2WSH from BIP173
+            ("0014751e76e8199196d454941c45d1b3a323f1433bd6", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"),
+            ("00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262", "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3", "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7"),
+        ];
+
+        for &(script, mainnet, testnet) in &cases {
+            assert_eq!(address(script, Network::Mainnet).as_deref(), Some(mainnet));
+            assert_eq!(address(script, Network::Testnet).as_deref(), Some(testnet));
+        }
+    }
+
+    #[test]
+    fn no_address() {
+        assert_eq!(address("6a0474657374", Network::Mainnet), None);
+        assert_eq!(address("51", Network::Mainnet), None);
+    }
+
+    #[test]
+    fn magic() {
+        for &network in &[Network::Mainnet, Network::Testnet] {
+            assert_eq!(Network::from_magic(network.magic()), Some(network));
+        }
+        assert_eq!(Network::Mainnet.magic().to_le_bytes(), [0xf9, 0xbe, 0xb4, 0xd9]);
+        assert_eq!(Network::Testnet.magic().to_le_bytes(), [0x0b, 0x11, 0x09, 0x07]);
+        assert_eq!(Network::from_magic(0), None);
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Amount of bitcoins stored as the number of satoshis.
+///
+/// Using this instead of plain `u64` avoids confusing satoshis with BTC. The
+/// arithmetic is checked since the values may come from untrusted data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct Amount(pub u64);
+
+impl Amount {
+    /// Zero satoshis.
+    pub const ZERO: Amount = Amount(0);
+
+    /// Number of satoshis in one bitcoin.
+    pub const ONE_BTC: Amount = Amount(100_000_000);
+
+    /// Maximum number of bitcoins that can ever exist, 21 million.
+    ///
+    /// Consensus rejects transactions with outputs above this, or summing above it.
+    pub const MAX_MONEY: Amount = Amount(21_000_000 * 100_000_000);
+
+    /// Creates the amount from the number of satoshis.
+    pub const fn from_sat(satoshis: u64) -> Self {
+        Amount(satoshis)
+    }
+
+    /// Returns the number of satoshis.
+    pub const fn to_sat(self) -> u64 {
+        self.0
+    }
+
+    /// Converts the amount in BTC, rounding to whole satoshis.
+    ///
+    /// Returns `None` if the value is negative, not a number or above `MAX_MONEY`.
+    pub fn from_btc(btc: f64) -> Option<Self> {
+        let satoshis = btc * Self::ONE_BTC.0 as f64;
+        // Also rejects NaN, since comparisons with it are false.
+        if !(0.0..=Self::MAX_MONEY.0 as f64).contains(&satoshis) {
+            return None;
+        }
+
+        // `f64::round` is not available without `std`, the value is not negative.
+        Some(Amount((satoshis + 0.5) as u64))
+    }
+
+    /// Returns the amount in BTC.
+    ///
+    /// The result is exact for amounts up to `MAX_MONEY`.
+    pub fn to_btc(self) -> f64 {
+        self.0 as f64 / Self::ONE_BTC.0 as f64
+    }
+
+    /// Adds the amounts, returning `None` on overflow.
+    pub fn checked_add(self, other: Amount) -> Option<Self> {
+        self.0.checked_add(other.0).map(Amount)
+    }
+
+    /// Subtracts the amounts, returning `None` if `other` is greater.
+    pub fn checked_sub(self, other: Amount) -> Option<Self> {
+        self.0.checked_sub(other.0).map(Amount)
+    }
+}
+
+impl From<u64> for Amount {
+    fn from(satoshis: u64) -> Self {
+        Amount(satoshis)
+    }
+}
+
+/// Displays the amount in BTC with all eight decimal places, e.g. `0.00010000 BTC`.
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{:08} BTC", self.0 / Self::ONE_BTC.0, self.0 % Self::ONE_BTC.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn btc_conversions() {
+        assert_eq!(Amount::from_btc(21_000_000.0), Some(Amount::MAX_MONEY));
+        assert_eq!(Amount::from_btc(21_000_000.000_000_01), None);
+        assert_eq!(Amount::from_btc(-0.000_000_01), None);
+        assert_eq!(Amount::from_btc(f64::NAN), None);
+        assert_eq!(Amount::from_btc(0.000_000_01), Some(Amount(1)));
+        assert_eq!(Amount::from_btc(0.1), Some(Amount(10_000_000)));
+        assert_eq!(Amount::MAX_MONEY.to_btc(), 21_000_000.0);
+        assert_eq!(Amount(1).to_btc(), 0.000_000_01);
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        assert_eq!(Amount(1).checked_sub(Amount(2)), None);
+        assert_eq!(Amount(2).checked_sub(Amount(2)), Some(Amount::ZERO));
+        assert_eq!(Amount(u64::MAX).checked_add(Amount(1)), None);
+        assert_eq!(Amount::MAX_MONEY.checked_add(Amount(1)), Some(Amount(2_100_000_000_000_001)));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Amount::MAX_MONEY.to_string(), "21000000.00000000 BTC");
+        assert_eq!(Amount(10_000).to_string(), "0.00010000 BTC");
+        assert_eq!(Amount::ZERO.to_string(), "0.00000000 BTC");
+    }
+}
@@ -0,0 +1,150 @@
+//! Conversions to and from types of the `bitcoin` crate.
+//!
+//! The conversions are lossless except for SegWit transactions with all
+//! witness stacks empty, which `bitcoin` serializes without witness. Their
+//! txid stays the same.
+
+use bitcoin::hashes::Hash;
+use crate::amount::Amount;
+use crate::hash::Hash256;
+use crate::script::Script;
+use crate::transaction::{Outpoint, TxInput, TxOutput, Transaction};
+use crate::witness::Witness;
+
+impl From<Amount> for bitcoin::Amount {
+    fn from(amount: Amount) -> Self {
+        bitcoin::Amount::from_sat(amount.0)
+    }
+}
+
+impl From<bitcoin::Amount> for Amount {
+    fn from(amount: bitcoin::Amount) -> Self {
+        Amount(amount.to_sat())
+    }
+}
+
+impl From<Outpoint> for bitcoin::OutPoint {
+    fn from(outpoint: Outpoint) -> Self {
+        bitcoin::OutPoint {
+            txid: bitcoin::Txid::from_byte_array(outpoint.txid.0),
+            vout: outpoint.index,
+        }
+    }
+}
+
+impl From<bitcoin::OutPoint> for Outpoint {
+    fn from(outpoint: bitcoin::OutPoint) -> Self {
+        Outpoint {
+            txid: Hash256(outpoint.txid.to_byte_array()),
+            index: outpoint.vout,
+        }
+    }
+}
+
+impl From<TxOutput> for bitcoin::TxOut {
+    fn from(output: TxOutput) -> Self {
+        bitcoin::TxOut {
+            value: output.satoshis.into(),
+            script_pubkey: bitcoin::ScriptBuf::from_bytes(output.verify_script.0),
+        }
+    }
+}
+
+impl From<bitcoin::TxOut> for TxOutput {
+    fn from(output: bitcoin::TxOut) -> Self {
+        TxOutput {
+            satoshis: output.value.into(),
+            verify_script: Script(output.script_pubkey.into_bytes()),
+        }
+    }
+}
+
+impl From<Transaction> for bitcoin::Transaction {
+    fn from(transaction: Transaction) -> Self {
+        // `bitcoin` keeps the witness in the input, empty for transactions without witness.
+        let mut witnesses = transaction.witnesses.into_iter();
+        let input = transaction.inputs
+            .into_iter()
+            .map(|input| bitcoin::TxIn {
+                previous_output: input.outpoint.into(),
+                script_sig: bitcoin::ScriptBuf::from_bytes(input.sig_script.0),
+                sequence: bitcoin::Sequence(input.sequence),
+                witness: witnesses.next().map(|witness| bitcoin::Witness::from_slice(&witness.0)).unwrap_or_default(),
+            })
+            .collect();
+
+        bitcoin::Transaction {
+            version: bitcoin::transaction::Version(transaction.version as i32),
+            lock_time: bitcoin::absolute::LockTime::from_consensus(transaction.lock_time),
+            input,
+            output: transaction.outputs.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<bitcoin::Transaction> for Transaction {
+    fn from(transaction: bitcoin::Transaction) -> Self {
+        let has_witness = transaction.input.iter().any(|input| !input.witness.is_empty());
+        let mut inputs = Vec::with_capacity(transaction.input.len());
+        let mut witnesses = Vec::new();
+        for input in transaction.input {
+            if has_witness {
+                witnesses.push(Witness(input.witness.to_vec()));
+            }
+            inputs.push(TxInput {
+                outpoint: input.previous_output.into(),
+                sig_script: Script(input.script_sig.into_bytes()),
+                sequence: input.sequence.0,
+            });
+        }
+
+        Transaction {
+            version: transaction.version.0 as u32,
+            inputs,
+            outputs: transaction.output.into_iter().map(Into::into).collect(),
+            lock_time: transaction.lock_time.to_consensus_u32(),
+            witnesses,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex;
+    use crate::test_vectors::*;
+
+    fn round_trip(hex: &str) -> (Transaction, Transaction) {
+        let transaction = Transaction::from_hex(hex).unwrap();
+        let converted = bitcoin::Transaction::from(transaction.clone());
+        assert_eq!(converted.compute_txid().to_byte_array(), transaction.txid().0);
+        assert_eq!(converted.compute_wtxid().to_byte_array(), transaction.wtxid().0);
+        (transaction, converted.into())
+    }
+
+    #[test]
+    fn legacy_round_trip() {
+        let (transaction, converted) = round_trip(TX_170);
+        assert_eq!(converted, transaction);
+        assert_eq!(converted.txid().to_string(), TX_170_TXID);
+    }
+
+    #[test]
+    fn segwit_round_trip() {
+        let (transaction, converted) = round_trip(BIP143_P2WPKH);
+        assert_eq!(converted, transaction);
+        assert_eq!(converted.txid().to_string(), BIP143_P2WPKH_TXID);
+        assert_eq!(converted.wtxid().to_string(), BIP143_P2WPKH_WTXID);
+        assert_eq!(bitcoin::consensus::encode::serialize(&bitcoin::Transaction::from(converted)), hex::decode(BIP143_P2WPKH).unwrap());
+    }
+
+    #[test]
+    fn empty_witnesses_are_dropped() {
+        let mut transaction = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        transaction.witnesses[1].0.clear();
+        let converted = Transaction::from(bitcoin::Transaction::from(transaction.clone()));
+        assert!(converted.witnesses.is_empty());
+        assert_eq!(converted.txid(), transaction.txid());
+        assert_eq!(converted.wtxid(), converted.txid());
+    }
+}
@@ -0,0 +1,232 @@
+#[cfg(feature = "std")]
+use byteorder::{LE, ReadBytesExt, WriteBytesExt};
+use std::error::Error;
+use std::fmt;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::{Read, Write};
+#[cfg(feature = "std")]
+use crate::error::ParseError;
+#[cfg(feature = "std")]
+use crate::limits::{self, Limits, SizeHint};
+#[cfg(feature = "std")]
+use crate::varint::{deserialize_varint, serialize_varint};
+use crate::prelude::*;
+use crate::hash::Hash256;
+#[cfg(feature = "std")]
+use crate::hash::Hash256Engine;
+use crate::transaction::Transaction;
+
+/// Contains the 80-byte header of a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockHeader {
+    pub version: u32,
+    /// Hash of the header of previous block
+    pub prev_block: Hash256,
+    /// Root of the merkle tree of transaction IDs
+    pub merkle_root: Hash256,
+    /// Unix timestamp set by the miner
+    pub time: u32,
+    /// Compact encoding of the difficulty target
+    pub bits: u32,
+    pub nonce: u32,
+}
+
+#[cfg(feature = "std")]
+impl BlockHeader {
+    /// Deserializes the header from the blockchain data
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let version = reader.read_u32::<LE>()?;
+        let prev_block = Hash256::deserialize(reader)?;
+        let merkle_root = Hash256::deserialize(reader)?;
+        let time = reader.read_u32::<LE>()?;
+        let bits = reader.read_u32::<LE>()?;
+        let nonce = reader.read_u32::<LE>()?;
+
+        Ok(BlockHeader {
+            version,
+            prev_block,
+            merkle_root,
+            time,
+            bits,
+            nonce,
+        })
+    }
+
+    /// Serializes the header the same way it's stored in the blockchain
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_u32::<LE>(self.version)?;
+        self.prev_block.serialize(writer)?;
+        self.merkle_root.serialize(writer)?;
+        writer.write_u32::<LE>(self.time)?;
+        writer.write_u32::<LE>(self.bits)?;
+        writer.write_u32::<LE>(self.nonce)
+    }
+}
+
+/// Contains whole block - the header and all transactions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub header: BlockHeader,
+    pub transactions: Vec<Transaction>,
+}
+
+#[cfg(feature = "std")]
+impl Block {
+    /// Deserializes the block from the blockchain data
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        Block::deserialize_with_limits(reader, &Limits::consensus())
+    }
+
+    /// Deserializes the block from the blockchain data, checking given limits for transactions.
+    pub fn deserialize_with_limits<R: Read>(reader: &mut R, limits: &Limits) -> Result<Self, ParseError> {
+        Block::deserialize_with_hint(reader, limits, &SizeHint::default())
+    }
+
+    /// Deserializes the block, allocating memory for transactions and their parts according to the hint.
+    pub fn deserialize_with_hint<R: Read>(reader: &mut R, limits: &Limits, hint: &SizeHint) -> Result<Self, ParseError> {
+        let header = BlockHeader::deserialize(reader)?;
+        let tx_count = deserialize_varint(reader)?;
+
+        // Sanity check. Since block can contain only 4M of bytes and each
+        // transaction has more than four bytes, this can't happen for valid block.
+        if tx_count > 1_000_000 {
+            return Err(ParseError::TooManyTransactions { count: tx_count });
+        }
+        let mut transactions = Vec::with_capacity(limits::preallocation_hinted(tx_count, hint.transactions));
+        for _ in 0..tx_count {
+            let transaction = Transaction::deserialize_with_hint(reader, limits, hint)?;
+            #[cfg(feature = "tracing")]
+            tracing::trace!(index = transactions.len(), size = transaction.total_size(), "parsed transaction");
+            transactions.push(transaction);
+        }
+
+        Ok(Block {
+            header,
+            transactions,
+        })
+    }
+
+    /// Serializes the block the same way it's stored in the blockchain
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.header.serialize(writer)?;
+        serialize_varint(writer, self.transactions.len() as u64)?;
+        for transaction in &self.transactions {
+            transaction.serialize(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Computes the merkle root of the transaction IDs.
+    ///
+    /// If a level has odd number of hashes the last one is paired with itself. This makes
+    /// the root ambiguous (CVE-2012-2459) but it's how Bitcoin defines it. Empty block results
+    /// in all-zero hash.
+    pub fn merkle_root(&self) -> Hash256 {
+        let mut hashes = self.transactions.iter().map(Transaction::txid).collect::<Vec<_>>();
+        if hashes.is_empty() {
+            return Hash256([0; 32]);
+        }
+
+        while hashes.len() > 1 {
+            hashes = hashes
+                .chunks(2)
+                .map(|pair| {
+                    let right = pair.last().expect("chunks are never empty");
+                    let mut engine = Hash256Engine::new();
+                    engine.input(&pair[0].0);
+                    engine.input(&right.0);
+                    engine.finalize()
+                })
+                .collect();
+        }
+        hashes.swap_remove(0)
+    }
+
+    /// Checks that the merkle root in the header matches the transactions.
+    ///
+    /// This detects corrupted transactions which still happen to parse. The
+    /// proof of work and other consensus rules aren't checked.
+    pub fn verify(&self) -> Result<(), BlockError> {
+        let computed = self.merkle_root();
+        if computed != self.header.merkle_root {
+            return Err(BlockError::MerkleMismatch {
+                computed,
+                expected: self.header.merkle_root.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when block doesn't match its header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockError {
+    /// Merkle root computed from the transactions differs from the one in the header.
+    MerkleMismatch { computed: Hash256, expected: Hash256 },
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BlockError::MerkleMismatch { ref computed, ref expected } => write!(f, "computed merkle root {} doesn't match {} in the header", computed, expected),
+        }
+    }
+}
+
+impl Error for BlockError {}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::hex;
+    use crate::test_vectors::*;
+
+    #[test]
+    fn genesis() {
+        let data = hex::decode(GENESIS_BLOCK).unwrap();
+        let block = Block::deserialize(&mut &*data).unwrap();
+
+        assert_eq!(block.header.version, 1);
+        assert_eq!(block.header.prev_block, Hash256([0; 32]));
+        assert_eq!(block.header.merkle_root.to_string(), "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b");
+        assert_eq!(block.header.time, 1231006505);
+        assert_eq!(block.header.bits, 0x1d00ffff);
+        assert_eq!(block.header.nonce, 2083236893);
+        assert_eq!(block.transactions.len(), 1);
+        assert_eq!(block.transactions[0].txid(), block.header.merkle_root);
+
+        let mut serialized = Vec::new();
+        block.serialize(&mut serialized).unwrap();
+        assert_eq!(serialized, data);
+    }
+
+    #[test]
+    fn merkle_root() {
+        let block = Block::deserialize(&mut &*hex::decode(BLOCK_TWO_TXS).unwrap()).unwrap();
+        assert_eq!(block.transactions.len(), 2);
+        assert_eq!(block.header.merkle_root.to_string(), BLOCK_TWO_TXS_MERKLE_ROOT);
+        assert_eq!(block.merkle_root(), block.header.merkle_root);
+
+        let genesis = Block::deserialize(&mut &*hex::decode(GENESIS_BLOCK).unwrap()).unwrap();
+        assert_eq!(genesis.merkle_root(), genesis.header.merkle_root);
+    }
+
+    #[test]
+    fn verify() {
+        let mut data = hex::decode(BLOCK_TWO_TXS).unwrap();
+        let block = Block::deserialize(&mut &*data).unwrap();
+        assert_eq!(block.verify(), Ok(()));
+
+        // the second byte of coinbase script, after header, transaction count,
+        // version, input count, outpoint and script length
+        data[80 + 1 + 4 + 1 + 36 + 1 + 1] ^= 1;
+        let corrupted = Block::deserialize(&mut &*data).unwrap();
+        let computed = corrupted.merkle_root();
+        assert_ne!(computed, block.header.merkle_root);
+        let error = corrupted.verify().unwrap_err();
+        assert_eq!(error, BlockError::MerkleMismatch { computed: computed.clone(), expected: block.header.merkle_root.clone() });
+        assert_eq!(error.to_string(), format!("computed merkle root {} doesn't match {} in the header", computed, BLOCK_TWO_TXS_MERKLE_ROOT));
+    }
+}
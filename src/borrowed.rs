@@ -0,0 +1,281 @@
+//! Zero-copy counterparts of the transaction types.
+//!
+//! These parse from a byte slice and borrow scripts and witness items from
+//! it instead of allocating a `Vec` for each of them. They are available
+//! without `std` too.
+
+use crate::cursor::Cursor;
+use crate::error::ParseError;
+use crate::amount::Amount;
+use crate::limits::{self, Limits};
+use crate::prelude::*;
+use crate::hash::Hash256;
+use crate::script::Script;
+use crate::witness;
+use crate::witness::Witness;
+use crate::transaction::{Outpoint, TxInput, TxOutput, Transaction};
+
+/// Script borrowed from the input buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptRef<'a>(pub &'a [u8]);
+
+impl<'a> ScriptRef<'a> {
+    /// Deserializes the script from the beginning of the data, advancing it.
+    pub fn deserialize(data: &mut &'a [u8]) -> Result<Self, ParseError> {
+        ScriptRef::deserialize_with_limits(data, &Limits::consensus())
+    }
+
+    /// Deserializes the script, checking length against given limits.
+    pub fn deserialize_with_limits(data: &mut &'a [u8], limits: &Limits) -> Result<Self, ParseError> {
+        let len = data.read_varint()?;
+        if len > limits.max_script_len {
+            return Err(ParseError::ScriptTooLong { len });
+        }
+
+        data.take_bytes(len).map(ScriptRef)
+    }
+
+    /// Copies the script into owned type.
+    pub fn to_owned(&self) -> Script {
+        Script(self.0.to_vec())
+    }
+}
+
+/// Witness stack borrowing its items from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WitnessRef<'a>(pub Vec<&'a [u8]>);
+
+impl<'a> WitnessRef<'a> {
+    /// Deserializes the witness stack from the beginning of the data, advancing it.
+    pub fn deserialize(data: &mut &'a [u8]) -> Result<Self, ParseError> {
+        let item_count = data.read_varint()?;
+        if item_count > witness::MAX_ITEMS {
+            return Err(ParseError::TooManyWitnessItems { count: item_count });
+        }
+
+        let mut items = Vec::with_capacity(limits::preallocation(item_count));
+        for _ in 0..item_count {
+            let len = data.read_varint()?;
+            if len > witness::MAX_ITEM_LEN {
+                return Err(ParseError::WitnessItemTooLong { len });
+            }
+            items.push(data.take_bytes(len)?);
+        }
+
+        Ok(WitnessRef(items))
+    }
+
+    /// Copies the witness into owned type.
+    pub fn to_owned(&self) -> Witness {
+        Witness(self.0.iter().map(|item| item.to_vec()).collect())
+    }
+}
+
+/// Transaction input borrowing its script from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxInputRef<'a> {
+    pub outpoint: Outpoint,
+    pub sig_script: ScriptRef<'a>,
+    pub sequence: u32,
+}
+
+impl<'a> TxInputRef<'a> {
+    /// Deserializes the input from the beginning of the data, advancing it.
+    pub fn deserialize(data: &mut &'a [u8]) -> Result<Self, ParseError> {
+        TxInputRef::deserialize_with_limits(data, &Limits::consensus())
+    }
+
+    /// Deserializes the input, checking given limits.
+    pub fn deserialize_with_limits(data: &mut &'a [u8], limits: &Limits) -> Result<Self, ParseError> {
+        let mut txid = [0; 32];
+        txid.copy_from_slice(data.take_bytes(32).map_err(ParseError::field("outpoint"))?);
+        let outpoint = Outpoint {
+            txid: Hash256(txid),
+            index: data.read_u32_le().map_err(ParseError::field("outpoint"))?,
+        };
+        let sig_script = ScriptRef::deserialize_with_limits(data, limits).map_err(ParseError::field("script"))?;
+        let sequence = data.read_u32_le().map_err(ParseError::field("sequence"))?;
+
+        Ok(TxInputRef {
+            outpoint,
+            sig_script,
+            sequence,
+        })
+    }
+
+    /// Copies the input into owned type.
+    pub fn to_owned(&self) -> TxInput {
+        TxInput {
+            outpoint: self.outpoint.clone(),
+            sig_script: self.sig_script.to_owned(),
+            sequence: self.sequence,
+        }
+    }
+}
+
+/// Transaction output borrowing its script from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxOutputRef<'a> {
+    pub satoshis: Amount,
+    pub verify_script: ScriptRef<'a>,
+}
+
+impl<'a> TxOutputRef<'a> {
+    /// Deserializes the output from the beginning of the data, advancing it.
+    pub fn deserialize(data: &mut &'a [u8]) -> Result<Self, ParseError> {
+        TxOutputRef::deserialize_with_limits(data, &Limits::consensus())
+    }
+
+    /// Deserializes the output, checking given limits.
+    pub fn deserialize_with_limits(data: &mut &'a [u8], limits: &Limits) -> Result<Self, ParseError> {
+        let satoshis = Amount(data.read_u64_le().map_err(ParseError::field("value"))?);
+        if satoshis > limits.max_output_value {
+            return Err(ParseError::InvalidAmount(satoshis.0));
+        }
+        let verify_script = ScriptRef::deserialize_with_limits(data, limits).map_err(ParseError::field("script"))?;
+
+        Ok(TxOutputRef {
+            satoshis,
+            verify_script,
+        })
+    }
+
+    /// Copies the output into owned type.
+    pub fn to_owned(&self) -> TxOutput {
+        TxOutput {
+            satoshis: self.satoshis,
+            verify_script: self.verify_script.to_owned(),
+        }
+    }
+}
+
+/// Transaction borrowing scripts and witness items from the input buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionRef<'a> {
+    pub version: u32,
+    pub inputs: Vec<TxInputRef<'a>>,
+    pub outputs: Vec<TxOutputRef<'a>>,
+    pub lock_time: u32,
+    /// Witness of each input, empty if the transaction was serialized without witness.
+    pub witnesses: Vec<WitnessRef<'a>>,
+}
+
+impl<'a> TransactionRef<'a> {
+    /// Deserializes the transaction from the beginning of the data, advancing it.
+    pub fn deserialize(data: &mut &'a [u8]) -> Result<Self, ParseError> {
+        TransactionRef::deserialize_with_limits(data, &Limits::consensus())
+    }
+
+    /// Deserializes the transaction, checking given limits.
+    ///
+    /// This follows the same rules as `Transaction::deserialize_with_limits`.
+    pub fn deserialize_with_limits(data: &mut &'a [u8], limits: &Limits) -> Result<Self, ParseError> {
+        let version = data.read_u32_le().map_err(ParseError::field("version"))?;
+        let mut input_count = data.read_varint().map_err(ParseError::field("input count"))?;
+
+        // Zero input count is always SegWit marker, see `Transaction::deserialize_with_limits`.
+        let has_witness = input_count == 0;
+        if has_witness {
+            let flag = data.read_u8().map_err(ParseError::field("SegWit flag"))?;
+            if flag != 1 {
+                return Err(ParseError::InvalidSegwitFlag { flag });
+            }
+            input_count = data.read_varint().map_err(ParseError::field("input count"))?;
+        }
+
+        if input_count > limits.max_inputs {
+            return Err(ParseError::TooManyInputs { count: input_count });
+        }
+        limits::checked_len(input_count)?;
+        let mut inputs = Vec::with_capacity(limits::preallocation(input_count));
+        for i in 0..input_count as usize {
+            inputs.push(TxInputRef::deserialize_with_limits(data, limits).map_err(ParseError::item("input", i))?);
+        }
+
+        let output_count = data.read_varint().map_err(ParseError::field("output count"))?;
+        if output_count > limits.max_outputs {
+            return Err(ParseError::TooManyOutputs { count: output_count });
+        }
+        limits::checked_len(output_count)?;
+        let mut outputs = Vec::with_capacity(limits::preallocation(output_count));
+        for i in 0..output_count as usize {
+            outputs.push(TxOutputRef::deserialize_with_limits(data, limits).map_err(ParseError::item("output", i))?);
+        }
+
+        let mut witnesses = Vec::new();
+        if has_witness {
+            witnesses.reserve_exact(inputs.len());
+            for i in 0..inputs.len() {
+                witnesses.push(WitnessRef::deserialize(data).map_err(ParseError::item("witness", i))?);
+            }
+        }
+        let lock_time = data.read_u32_le().map_err(ParseError::field("lock time"))?;
+
+        Ok(TransactionRef {
+            version,
+            inputs,
+            outputs,
+            lock_time,
+            witnesses,
+        })
+    }
+
+    /// Copies the transaction into owned type.
+    pub fn to_owned(&self) -> Transaction {
+        Transaction {
+            version: self.version,
+            inputs: self.inputs.iter().map(TxInputRef::to_owned).collect(),
+            outputs: self.outputs.iter().map(TxOutputRef::to_owned).collect(),
+            lock_time: self.lock_time,
+            witnesses: self.witnesses.iter().map(WitnessRef::to_owned).collect(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::hex;
+    use crate::test_vectors::*;
+
+    #[test]
+    fn same_as_owned() {
+        for hex in &[TX_170, BIP143_P2WPKH] {
+            let data = hex::decode(hex).unwrap();
+            let mut borrowed_data = &data[..];
+            let borrowed = TransactionRef::deserialize(&mut borrowed_data).unwrap();
+            assert!(borrowed_data.is_empty());
+            assert_eq!(borrowed.to_owned(), Transaction::deserialize(&mut &data[..]).unwrap());
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        let data = hex::decode(BIP143_P2WPKH).unwrap();
+        for len in 0..data.len() {
+            let error = TransactionRef::deserialize(&mut &data[..len]).unwrap_err();
+            assert!(error.is_unexpected_eof(), "length {}: {:?}", len, error);
+        }
+    }
+
+    #[test]
+    fn limits() {
+        let data = hex::decode(BIP143_P2WPKH).unwrap();
+
+        let limits = Limits {
+            max_inputs: 1,
+            ..Limits::consensus()
+        };
+        let error = TransactionRef::deserialize_with_limits(&mut &data[..], &limits).unwrap_err();
+        assert!(matches!(error.root_cause(), ParseError::TooManyInputs { count: 2 }), "{:?}", error);
+
+        // the first input spends P2PK with 73 bytes long script
+        let limits = Limits {
+            max_script_len: 72,
+            ..Limits::consensus()
+        };
+        let error = TransactionRef::deserialize_with_limits(&mut &data[..], &limits).unwrap_err();
+        assert!(matches!(error.root_cause(), ParseError::ScriptTooLong { len: 73 }), "{:?}", error);
+        assert!(matches!(error, ParseError::Context { context: "input", index: Some(0), .. }), "{:?}", error);
+    }
+}
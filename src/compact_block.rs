@@ -0,0 +1,149 @@
+//! Compact blocks used for block relay. (BIP152)
+
+use byteorder::{LE, ReadBytesExt, WriteBytesExt};
+use std::io;
+use std::io::{Read, Write};
+use crate::error::ParseError;
+use crate::limits;
+use crate::reader::read_array;
+use crate::varint::{deserialize_varint, serialize_varint};
+use crate::block::BlockHeader;
+use crate::transaction::Transaction;
+
+/// Sanity check, same as for transactions in block.
+const MAX_TRANSACTIONS: u64 = 1_000_000;
+
+/// Transaction sent in full within compact block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefilledTransaction {
+    /// Position in block, differentially encoded since the previous prefilled transaction
+    pub index: u64,
+    pub transaction: Transaction,
+}
+
+impl PrefilledTransaction {
+    /// Deserializes the prefilled transaction from network data
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let index = deserialize_varint(reader)?;
+        let transaction = Transaction::deserialize(reader)?;
+
+        Ok(PrefilledTransaction {
+            index,
+            transaction,
+        })
+    }
+
+    /// Serializes the prefilled transaction the same way it's sent over network
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        serialize_varint(writer, self.index)?;
+        self.transaction.serialize(writer)
+    }
+}
+
+/// Contents of `cmpctblock` message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactBlock {
+    pub header: BlockHeader,
+    /// Nonce used for computing short IDs
+    pub nonce: u64,
+    /// Short IDs of transactions not sent in full
+    pub short_ids: Vec<[u8; 6]>,
+    pub prefilled: Vec<PrefilledTransaction>,
+}
+
+impl CompactBlock {
+    /// Deserializes the compact block from network data
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let header = BlockHeader::deserialize(reader)?;
+        let nonce = reader.read_u64::<LE>()?;
+
+        let short_id_count = deserialize_varint(reader)?;
+        if short_id_count > MAX_TRANSACTIONS {
+            return Err(ParseError::TooManyTransactions { count: short_id_count });
+        }
+        let mut short_ids = Vec::with_capacity(limits::preallocation(short_id_count));
+        for _ in 0..short_id_count {
+            short_ids.push(read_array(reader)?);
+        }
+
+        let prefilled_count = deserialize_varint(reader)?;
+        if prefilled_count > MAX_TRANSACTIONS {
+            return Err(ParseError::TooManyTransactions { count: prefilled_count });
+        }
+        let mut prefilled = Vec::with_capacity(limits::preallocation(prefilled_count));
+        for _ in 0..prefilled_count {
+            prefilled.push(PrefilledTransaction::deserialize(reader)?);
+        }
+
+        Ok(CompactBlock {
+            header,
+            nonce,
+            short_ids,
+            prefilled,
+        })
+    }
+
+    /// Serializes the compact block the same way it's sent over network
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.header.serialize(writer)?;
+        writer.write_u64::<LE>(self.nonce)?;
+        serialize_varint(writer, self.short_ids.len() as u64)?;
+        for short_id in &self.short_ids {
+            writer.write_all(short_id)?;
+        }
+        serialize_varint(writer, self.prefilled.len() as u64)?;
+        for prefilled in &self.prefilled {
+            prefilled.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+    use crate::hex;
+    use crate::test_vectors::*;
+
+    const NONCE: u64 = 0x0123_4567_89ab_cdef;
+
+    fn compact_block() -> CompactBlock {
+        let block = Block::deserialize(&mut &*hex::decode(BLOCK_TWO_TXS).unwrap()).unwrap();
+        CompactBlock {
+            header: block.header,
+            nonce: NONCE,
+            short_ids: vec![[1, 2, 3, 4, 5, 6]],
+            prefilled: vec![PrefilledTransaction {
+                index: 0,
+                transaction: block.transactions[0].clone(),
+            }],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let compact = compact_block();
+        let mut data = Vec::new();
+        compact.serialize(&mut data).unwrap();
+        assert_eq!(data[80..88], NONCE.to_le_bytes());
+        // count and the short ID
+        assert_eq!(data[88..95], [1, 1, 2, 3, 4, 5, 6]);
+        // count and index of the coinbase
+        assert_eq!(data[95..97], [1, 0]);
+
+        let mut reader = &*data;
+        assert_eq!(CompactBlock::deserialize(&mut reader).unwrap(), compact);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn truncated_short_id() {
+        let mut data = Vec::new();
+        compact_block().serialize(&mut data).unwrap();
+
+        // ends in the middle of the short ID
+        let error = CompactBlock::deserialize(&mut &data[..92]).unwrap_err();
+        assert!(matches!(error, ParseError::UnexpectedEof), "{:?}", error);
+    }
+}
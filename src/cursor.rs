@@ -0,0 +1,44 @@
+//! Reading of primitive values from byte slices, which works without `std`.
+
+use byteorder::{ByteOrder, LE};
+use crate::error::ParseError;
+use crate::limits;
+use crate::varint::VarInt;
+
+/// Reads values from the beginning of a byte slice, advancing it.
+pub(crate) trait Cursor<'a> {
+    /// Takes `len` bytes from the beginning of the data.
+    fn take_bytes(&mut self, len: u64) -> Result<&'a [u8], ParseError>;
+
+    fn read_u8(&mut self) -> Result<u8, ParseError> {
+        self.take_bytes(1).map(|bytes| bytes[0])
+    }
+
+    fn read_u32_le(&mut self) -> Result<u32, ParseError> {
+        self.take_bytes(4).map(LE::read_u32)
+    }
+
+    fn read_u64_le(&mut self) -> Result<u64, ParseError> {
+        self.take_bytes(8).map(LE::read_u64)
+    }
+
+    /// Reads "varint" as defined by Bitcoin protocol.
+    fn read_varint(&mut self) -> Result<u64, ParseError> {
+        let first = self.read_u8()?;
+        let rest = self.take_bytes(VarInt::remaining_len(first) as u64)?;
+        Ok(VarInt::decode(first, rest).0)
+    }
+}
+
+impl<'a> Cursor<'a> for &'a [u8] {
+    fn take_bytes(&mut self, len: u64) -> Result<&'a [u8], ParseError> {
+        let len = limits::checked_len(len)?;
+        if len > self.len() {
+            return Err(ParseError::UnexpectedEof);
+        }
+
+        let (taken, rest) = self.split_at(len);
+        *self = rest;
+        Ok(taken)
+    }
+}
@@ -0,0 +1,291 @@
+use std::error::Error;
+use std::fmt;
+#[cfg(feature = "std")]
+use std::io;
+use crate::hex::HexError;
+#[cfg(feature = "std")]
+use crate::reader::SizeLimitExceeded;
+use crate::prelude::*;
+
+/// Error returned when deserializing fails.
+#[derive(Debug)]
+pub enum ParseError {
+    /// Reading from the underlying reader failed.
+    #[cfg(feature = "std")]
+    Io(io::Error),
+    /// The data ended before the whole structure was read.
+    UnexpectedEof,
+    /// Script is longer than allowed by consensus.
+    ScriptTooLong { len: u64 },
+    /// Transaction claims more inputs than can fit into a block.
+    TooManyInputs { count: u64 },
+    /// Transaction claims more outputs than can fit into a block.
+    TooManyOutputs { count: u64 },
+    /// Block claims more transactions than can fit into it.
+    TooManyTransactions { count: u64 },
+    /// Witness stack claims more items than can fit into a block.
+    TooManyWitnessItems { count: u64 },
+    /// Witness item is longer than can fit into a block.
+    WitnessItemTooLong { len: u64 },
+    /// Varint used longer encoding than necessary.
+    NonCanonicalVarint { value: u64 },
+    /// SegWit marker was followed by unknown flag.
+    InvalidSegwitFlag { flag: u8 },
+    /// Inventory has more items than allowed.
+    TooManyInventoryItems { count: u64 },
+    /// `addr` message has more addresses than allowed.
+    TooManyAddresses { count: u64 },
+    /// `headers` message has more headers than allowed.
+    TooManyHeaders { count: u64 },
+    /// Header in `headers` message is followed by non-zero transaction count.
+    UnexpectedTransactionCount { count: u64 },
+    /// Block locator has more hashes than allowed.
+    TooManyLocatorHashes { count: u64 },
+    /// Network message has different command than expected.
+    UnexpectedCommand { command: [u8; 12] },
+    /// Block file contains unknown network magic.
+    InvalidMagic { magic: u32 },
+    /// Block in a block file is longer than allowed.
+    BlockTooLarge { len: u32 },
+    /// Network message payload is longer than allowed.
+    PayloadTooLong { len: u32 },
+    /// Checksum of network message payload doesn't match.
+    ChecksumMismatch,
+    /// The data has different length than the parsed structure.
+    LengthMismatch { expected: usize, actual: usize },
+    /// Length or count doesn't fit into `usize` of this platform.
+    LengthOverflow { len: u64 },
+    /// Transaction has no inputs, which is forbidden by consensus.
+    NoInputs,
+    /// Transaction has no outputs, which is forbidden by consensus.
+    NoOutputs,
+    /// Output value exceeds 21 million bitcoins, contains the value in satoshis.
+    InvalidAmount(u64),
+    /// Sum of output values exceeds 21 million bitcoins.
+    TotalOutputTooLarge,
+    /// Multiple inputs spend the same outpoint.
+    DuplicateInputs,
+    /// Transaction weight exceeds the weight limit of a block.
+    TransactionTooLarge { weight: usize },
+    /// More bytes were read than the limit allows.
+    SizeLimitExceeded { limit: u64 },
+    /// Data continues after the parsed structure, contains the number of remaining bytes.
+    TrailingBytes(usize),
+    /// The input string is not valid hex.
+    InvalidHex(HexError),
+    /// String is longer than allowed.
+    StringTooLong { len: u64 },
+    /// String is not valid UTF-8.
+    InvalidUtf8,
+    /// Parsing failed after reading given number of bytes.
+    ///
+    /// Returned by functions tracking the position, such as
+    /// `Transaction::deserialize_tracking_offset`. Use `HexDump` to see the data around it.
+    AtOffset { offset: usize, error: Box<ParseError> },
+    /// Reading of the field described by `context` failed.
+    ///
+    /// `index` is the position of the item, such as input, in its list. Contexts
+    /// nest from the outermost structure, use `root_cause` to get the original error.
+    ///
+    /// Errors of transaction fields are wrapped in this, so `UnexpectedEof` and
+    /// other variants are no longer returned directly when a transaction fails to
+    /// parse. Match on `root_cause` instead, or use `is_unexpected_eof`.
+    Context { context: &'static str, index: Option<usize>, error: Box<ParseError> },
+}
+
+impl ParseError {
+    /// Returns the byte offset at which parsing failed, if it was tracked.
+    pub fn offset(&self) -> Option<usize> {
+        match *self {
+            ParseError::AtOffset { offset, .. } => Some(offset),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without the offset.
+    pub fn without_offset(&self) -> &ParseError {
+        match *self {
+            ParseError::AtOffset { ref error, .. } => error,
+            ref error => error,
+        }
+    }
+
+    /// Returns the original error without any offset or context.
+    pub fn root_cause(&self) -> &ParseError {
+        match *self {
+            ParseError::AtOffset { ref error, .. } | ParseError::Context { ref error, .. } => error.root_cause(),
+            ref error => error,
+        }
+    }
+
+    /// Checks whether the data ended too early, even if the error is wrapped in context or offset.
+    pub fn is_unexpected_eof(&self) -> bool {
+        matches!(self.root_cause(), ParseError::UnexpectedEof)
+    }
+
+    /// Returns a function wrapping the error in the context of given field, intended for `map_err`.
+    pub(crate) fn field<E: Into<ParseError>>(context: &'static str) -> impl FnOnce(E) -> ParseError {
+        move |error| ParseError::Context { context, index: None, error: Box::new(error.into()) }
+    }
+
+    /// Same as `field` for an item at `index` in a list.
+    pub(crate) fn item<E: Into<ParseError>>(context: &'static str, index: usize) -> impl FnOnce(E) -> ParseError {
+        move |error| ParseError::Context { context, index: Some(index), error: Box::new(error.into()) }
+    }
+
+    /// Attaches the offset to the error, replacing previous one.
+    #[cfg(feature = "std")]
+    pub(crate) fn at_offset(self, offset: usize) -> Self {
+        let error = match self {
+            ParseError::AtOffset { error, .. } => error,
+            error => Box::new(error),
+        };
+        ParseError::AtOffset { offset, error }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for ParseError {
+    fn from(error: io::Error) -> Self {
+        if error.kind() == io::ErrorKind::UnexpectedEof {
+            return ParseError::UnexpectedEof;
+        }
+
+        match error.get_ref().and_then(|inner| inner.downcast_ref::<SizeLimitExceeded>()) {
+            Some(exceeded) => ParseError::SizeLimitExceeded { limit: exceeded.limit },
+            None => ParseError::Io(error),
+        }
+    }
+}
+
+impl From<HexError> for ParseError {
+    fn from(error: HexError) -> Self {
+        ParseError::InvalidHex(error)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            #[cfg(feature = "std")]
+            ParseError::Io(ref error) => write!(f, "failed to read data: {}", error),
+            ParseError::UnexpectedEof => write!(f, "unexpected end of data"),
+            ParseError::ScriptTooLong { len } => write!(f, "script length {} exceeds the limit", len),
+            ParseError::TooManyInputs { count } => write!(f, "input count {} exceeds the limit", count),
+            ParseError::TooManyOutputs { count } => write!(f, "output count {} exceeds the limit", count),
+            ParseError::TooManyTransactions { count } => write!(f, "transaction count {} exceeds the limit", count),
+            ParseError::TooManyWitnessItems { count } => write!(f, "witness item count {} exceeds the limit", count),
+            ParseError::WitnessItemTooLong { len } => write!(f, "witness item length {} exceeds the limit", len),
+            ParseError::NonCanonicalVarint { value } => write!(f, "varint {} is not encoded canonically", value),
+            ParseError::InvalidSegwitFlag { flag } => write!(f, "invalid SegWit flag {}", flag),
+            ParseError::TooManyInventoryItems { count } => write!(f, "inventory item count {} exceeds the limit", count),
+            ParseError::TooManyAddresses { count } => write!(f, "address count {} exceeds the limit", count),
+            ParseError::TooManyHeaders { count } => write!(f, "header count {} exceeds the limit", count),
+            ParseError::UnexpectedTransactionCount { count } => write!(f, "header followed by transaction count {} instead of zero", count),
+            ParseError::TooManyLocatorHashes { count } => write!(f, "locator hash count {} exceeds the limit", count),
+            ParseError::UnexpectedCommand { ref command } => {
+                let len = command.iter().position(|&byte| byte == 0).unwrap_or(command.len());
+                write!(f, "unexpected command {:?}", String::from_utf8_lossy(&command[..len]))
+            },
+            ParseError::InvalidMagic { magic } => write!(f, "unknown network magic {:#010x}", magic),
+            ParseError::BlockTooLarge { len } => write!(f, "block length {} exceeds the limit", len),
+            ParseError::PayloadTooLong { len } => write!(f, "payload length {} exceeds the limit", len),
+            ParseError::ChecksumMismatch => write!(f, "payload checksum doesn't match"),
+            ParseError::LengthMismatch { expected, actual } => write!(f, "expected {} bytes, parsed {}", expected, actual),
+            ParseError::LengthOverflow { len } => write!(f, "length {} doesn't fit into memory of this platform", len),
+            ParseError::NoInputs => write!(f, "transaction has no inputs"),
+            ParseError::NoOutputs => write!(f, "transaction has no outputs"),
+            ParseError::InvalidAmount(value) => write!(f, "output value {} satoshis exceeds 21 million bitcoins", value),
+            ParseError::TotalOutputTooLarge => write!(f, "sum of output values exceeds 21 million bitcoins"),
+            ParseError::DuplicateInputs => write!(f, "transaction spends the same outpoint multiple times"),
+            ParseError::TransactionTooLarge { weight } => write!(f, "transaction weight {} exceeds the limit", weight),
+            ParseError::SizeLimitExceeded { limit } => write!(f, "read more than {} bytes", limit),
+            ParseError::TrailingBytes(len) => write!(f, "{} bytes remain after the parsed data", len),
+            ParseError::InvalidHex(ref error) => write!(f, "invalid hex: {}", error),
+            ParseError::StringTooLong { len } => write!(f, "string length {} exceeds the limit", len),
+            ParseError::InvalidUtf8 => write!(f, "string is not valid UTF-8"),
+            ParseError::AtOffset { offset, ref error } => write!(f, "{} at byte {}", error, offset),
+            ParseError::Context { context, index: Some(index), ref error } => write!(f, "{} {}: {}", context, index, error),
+            ParseError::Context { context, index: None, ref error } => write!(f, "{}: {}", context, error),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            #[cfg(feature = "std")]
+            ParseError::Io(ref error) => Some(error),
+            ParseError::InvalidHex(ref error) => Some(error),
+            ParseError::AtOffset { ref error, .. } | ParseError::Context { ref error, .. } => Some(&**error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::transaction::Transaction;
+
+    #[test]
+    fn from_io_error() {
+        let eof = io::Error::from(io::ErrorKind::UnexpectedEof);
+        assert!(matches!(ParseError::from(eof), ParseError::UnexpectedEof));
+
+        let error = ParseError::from(io::Error::other("disconnected"));
+        assert!(matches!(error, ParseError::Io(_)));
+        let source = error.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(source.kind(), io::ErrorKind::Other);
+    }
+
+    #[test]
+    fn early_eof() {
+        let error = Transaction::deserialize(&mut &[0x01, 0x00, 0x00][..]).unwrap_err();
+        assert!(matches!(error.root_cause(), ParseError::UnexpectedEof));
+        assert!(error.is_unexpected_eof());
+    }
+
+    #[test]
+    fn context_of_truncated_output() {
+        let data = crate::hex::decode(crate::test_vectors::TX_170).unwrap();
+        // in the script of the second output
+        let error = Transaction::deserialize(&mut &data[..data.len() - 10]).unwrap_err();
+        assert!(error.is_unexpected_eof());
+        match error {
+            ParseError::Context { context: "output", index: Some(1), ref error } => {
+                assert!(matches!(**error, ParseError::Context { context: "script", index: None, .. }));
+            },
+            ref error => panic!("unexpected error {:?}", error),
+        }
+        assert_eq!(error.to_string(), "output 1: script: unexpected end of data");
+
+        assert!(!ParseError::NoInputs.is_unexpected_eof());
+        assert!(!ParseError::from(io::Error::other("disconnected")).is_unexpected_eof());
+    }
+
+    #[test]
+    fn offset() {
+        let data = crate::hex::decode(crate::test_vectors::BIP143_P2WPKH).unwrap();
+
+        // the data ends right after the marker
+        let error = Transaction::deserialize_tracking_offset(&mut &data[..5]).unwrap_err();
+        assert_eq!(error.offset(), Some(5));
+        assert!(error.without_offset().is_unexpected_eof());
+
+        let mut invalid_flag = data.clone();
+        invalid_flag[5] = 0x00;
+        let error = Transaction::deserialize_tracking_offset(&mut &*invalid_flag).unwrap_err();
+        assert_eq!(error.offset(), Some(6));
+        assert!(matches!(error.without_offset(), ParseError::InvalidSegwitFlag { flag: 0 }));
+        assert_eq!(error.to_string(), "invalid SegWit flag 0 at byte 6");
+
+        // the later offset replaces the earlier one
+        let error = error.at_offset(42);
+        assert_eq!(error.offset(), Some(42));
+        assert!(matches!(error.without_offset(), ParseError::InvalidSegwitFlag { flag: 0 }));
+
+        assert!(Transaction::deserialize_tracking_offset(&mut &data[..]).is_ok());
+        assert_eq!(ParseError::NoInputs.offset(), None);
+    }
+}
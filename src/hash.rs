@@ -0,0 +1,348 @@
+use sha2::{Sha256, Digest};
+use ripemd::Ripemd160;
+use std::array::TryFromSliceError;
+use std::convert::TryFrom;
+use std::fmt;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::{Read, Write};
+use std::str::FromStr;
+#[cfg(feature = "std")]
+use crate::error::ParseError;
+#[cfg(feature = "std")]
+use crate::reader::read_array;
+use crate::prelude::*;
+use crate::hex;
+use crate::hex::HexError;
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Represents 256 bit hash. (SHA256)
+///
+/// The bytes are stored in the same order as they are serialized and as
+/// SHA256 outputs them. Bitcoin Core displays them reversed, so `Display`,
+/// `LowerHex` and `FromStr` reverse them too. Don't reverse them when
+/// converting from other representation, unless it's the displayed hex.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Hash256(pub [u8; 32]);
+
+impl Hash256 {
+    /// Hashes the data using SHA256 once, as used by P2WSH.
+    pub fn sha256(data: &[u8]) -> Self {
+        Hash256(Sha256::digest(data).into())
+    }
+
+    /// Hashes the data using SHA256 twice, as Bitcoin does.
+    pub fn double_sha256(data: &[u8]) -> Self {
+        let mut engine = Hash256Engine::new();
+        engine.input(data);
+        engine.finalize()
+    }
+
+    /// Parses the hash from hex in reversed byte order, as txids are displayed.
+    ///
+    /// Same as `FromStr`, but makes the byte order explicit at the call site.
+    pub fn from_txid_str(s: &str) -> Result<Self, HexError> {
+        s.parse()
+    }
+
+    /// Creates the hash from bytes in the order they are serialized in.
+    ///
+    /// Same as `Hash256(bytes)`, but makes the byte order explicit at the call site.
+    pub const fn from_internal_bytes(bytes: [u8; 32]) -> Self {
+        Hash256(bytes)
+    }
+
+    /// Returns the bytes in the order they are serialized in.
+    pub const fn to_internal_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    /// Formats the hash as hex in reversed byte order.
+    ///
+    /// This is the order used by Bitcoin Core and block explorers.
+    pub fn to_reversed_hex(&self) -> String {
+        self.to_string()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Hash256 {
+    /// Deserializes the hash
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        Ok(Hash256(read_array(reader)?))
+    }
+
+    /// Serializes the hash
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.0)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Hash256 {
+    /// Deserializes the hash from an async reader
+    pub async fn deserialize_async<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self, ParseError> {
+        let mut buf = [0; 32];
+        reader.read_exact(&mut buf).await?;
+
+        Ok(Hash256(buf))
+    }
+}
+
+/// Prints the hash in reversed hex, since raw bytes are unreadable.
+impl fmt::Debug for Hash256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Hash256({})", self)
+    }
+}
+
+/// Displays the hash as hex in reversed byte order, same as Bitcoin Core.
+impl fmt::Display for Hash256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+impl fmt::LowerHex for Hash256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        hex::write(f, self.0.iter().rev())
+    }
+}
+
+/// Parses the hash from reversed hex, as displayed by Bitcoin Core.
+///
+/// Optional `0x` prefix and whitespace are ignored.
+impl FromStr for Hash256 {
+    type Err = HexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut buf = [0; 32];
+        hex::decode_lenient_to_slice(s, &mut buf)?;
+        buf.reverse();
+
+        Ok(Hash256(buf))
+    }
+}
+
+/// Creates the hash from raw bytes, the slice must be exactly 32 bytes long.
+impl<'a> TryFrom<&'a [u8]> for Hash256 {
+    type Error = TryFromSliceError;
+
+    fn try_from(bytes: &'a [u8]) -> Result<Self, Self::Error> {
+        <[u8; 32]>::try_from(bytes).map(Hash256)
+    }
+}
+
+/// Hashes the data using SHA256 twice, same as `Hash256::double_sha256`.
+pub fn hash256(data: &[u8]) -> Hash256 {
+    Hash256::double_sha256(data)
+}
+
+/// Hashes the data using SHA256 and then RIPEMD160, same as `Hash160::hash`.
+pub fn hash160(data: &[u8]) -> Hash160 {
+    Hash160::hash(data)
+}
+
+/// Computes double SHA256 of data supplied in parts.
+///
+/// With `std` it implements `Write`, so objects can be serialized directly
+/// into it without storing them in a buffer.
+#[derive(Debug, Clone, Default)]
+pub struct Hash256Engine(Sha256);
+
+impl Hash256Engine {
+    /// Creates the engine with no data hashed yet.
+    pub fn new() -> Self {
+        Hash256Engine::default()
+    }
+
+    /// Adds the data to the hashed data.
+    pub fn input(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    /// Finishes hashing and returns the hash.
+    pub fn finalize(self) -> Hash256 {
+        let hash = Sha256::digest(self.0.finalize());
+        let mut buf = [0; 32];
+        buf.copy_from_slice(&hash);
+
+        Hash256(buf)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Write for Hash256Engine {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Represents 160 bit hash. (RIPEMD160 of SHA256)
+///
+/// Used for public key and script hashes in P2PKH, P2SH and P2WPKH outputs.
+/// Unlike `Hash256`, these are displayed in the same order as they are stored.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Hash160(pub [u8; 20]);
+
+impl Hash160 {
+    /// Hashes the data using SHA256 and then RIPEMD160, as Bitcoin does.
+    pub fn hash(data: &[u8]) -> Self {
+        Hash160(Ripemd160::digest(Sha256::digest(data)).into())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Hash160 {
+    /// Deserializes the hash
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        Ok(Hash160(read_array(reader)?))
+    }
+
+    /// Serializes the hash
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.0)
+    }
+}
+
+impl fmt::Debug for Hash160 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Hash160({})", self)
+    }
+}
+
+impl fmt::Display for Hash160 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+impl fmt::LowerHex for Hash160 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        hex::write(f, &self.0)
+    }
+}
+
+/// Parses the hash from hex, ignoring optional `0x` prefix and whitespace.
+impl FromStr for Hash160 {
+    type Err = HexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut buf = [0; 20];
+        hex::decode_lenient_to_slice(s, &mut buf)?;
+
+        Ok(Hash160(buf))
+    }
+}
+
+/// Creates the hash from raw bytes, the slice must be exactly 20 bytes long.
+impl<'a> TryFrom<&'a [u8]> for Hash160 {
+    type Error = TryFromSliceError;
+
+    fn try_from(bytes: &'a [u8]) -> Result<Self, Self::Error> {
+        <[u8; 20]>::try_from(bytes).map(Hash160)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_vectors::TX_170_TXID as TXID;
+
+    #[test]
+    fn from_str() {
+        let hash = TXID.parse::<Hash256>().unwrap();
+        assert_eq!(hash.to_string(), TXID);
+        // displayed reversed
+        assert_eq!(hash.0[0], 0x16);
+        assert_eq!(format!("{:x}", hash), TXID);
+        assert_eq!(format!("0x{}", TXID).parse::<Hash256>(), Ok(hash));
+    }
+
+    #[test]
+    fn from_str_errors() {
+        assert_eq!(TXID[..62].parse::<Hash256>(), Err(HexError::InvalidLength { expected: 64, actual: 62 }));
+        assert_eq!(format!("{}00", TXID).parse::<Hash256>(), Err(HexError::InvalidLength { expected: 64, actual: 66 }));
+        assert_eq!(TXID[..63].parse::<Hash256>(), Err(HexError::OddLength));
+        assert_eq!(TXID.replacen('f', "g", 1).parse::<Hash256>(), Err(HexError::InvalidChar { index: 0 }));
+    }
+
+    #[test]
+    fn txid_byte_order() {
+        let hash = Hash256::from_txid_str(TXID).unwrap();
+        assert_eq!(hash.to_string(), TXID);
+
+        let mut internal = crate::hex::decode(TXID).unwrap();
+        internal.reverse();
+        assert_eq!(hash.to_internal_bytes()[..], internal[..]);
+
+        let bytes = hash.to_internal_bytes();
+        assert_eq!(Hash256::from_internal_bytes(bytes).to_internal_bytes(), bytes);
+        assert_eq!(Hash256::from_internal_bytes(bytes), hash);
+    }
+
+    #[test]
+    fn try_from_slice() {
+        let bytes = [0xab; 33];
+        assert_eq!(Hash256::try_from(&bytes[..32]).unwrap(), Hash256([0xab; 32]));
+        assert!(Hash256::try_from(&bytes[..31]).is_err());
+        assert!(Hash256::try_from(&bytes[..]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn deserialize_fixed_sizes() {
+        let mut data = Vec::new();
+        data.extend((0..20).chain(0..32));
+        let mut reader = &*data;
+
+        let hash160 = Hash160::deserialize(&mut reader).unwrap();
+        assert_eq!(hash160.0[..], data[..20]);
+        assert_eq!(hash160.to_string(), "000102030405060708090a0b0c0d0e0f10111213");
+
+        let hash256 = Hash256::deserialize(&mut reader).unwrap();
+        assert_eq!(hash256.0[..], data[20..]);
+        assert_eq!(hash256.to_string(), "1f1e1d1c1b1a191817161514131211100f0e0d0c0b0a09080706050403020100");
+        assert!(reader.is_empty());
+
+        assert!(matches!(Hash160::deserialize(&mut &data[..19]), Err(ParseError::UnexpectedEof)));
+        assert!(matches!(Hash256::deserialize(&mut &data[..31]), Err(ParseError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn hash256_of_empty() {
+        // in the order SHA256 outputs it, displayed reversed
+        let expected = crate::hex::decode("5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456").unwrap();
+        assert_eq!(hash256(b"").0[..], expected[..]);
+        assert_eq!(Hash256Engine::new().finalize(), hash256(b""));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn engine_streams() {
+        let mut engine = Hash256Engine::new();
+        engine.write_all(b"hello ").unwrap();
+        engine.write_all(b"world").unwrap();
+        assert_eq!(engine.finalize(), hash256(b"hello world"));
+    }
+
+    #[test]
+    fn hash160_of_pubkey() {
+        // key of the P2WPKH input in BIP143
+        let pubkey = crate::hex::decode("025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeee6357").unwrap();
+        assert_eq!(hash160(&pubkey).to_string(), "1d0f172a0ecb48aee1be1f2687d2963ae33f71a1");
+    }
+}
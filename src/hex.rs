@@ -0,0 +1,238 @@
+use std::error::Error;
+use std::cmp;
+use std::fmt;
+use crate::prelude::*;
+
+/// Error returned when decoding hex string fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HexError {
+    /// The string has odd number of hex digits.
+    OddLength,
+    /// The string has different number of hex digits than expected.
+    InvalidLength { expected: usize, actual: usize },
+    /// The string contains a character which isn't a hex digit at given byte index.
+    InvalidChar { index: usize },
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HexError::OddLength => write!(f, "odd number of hex digits"),
+            HexError::InvalidLength { expected, actual } => write!(f, "expected {} hex digits, got {}", expected, actual),
+            HexError::InvalidChar { index } => write!(f, "invalid hex digit at index {}", index),
+        }
+    }
+}
+
+impl Error for HexError {}
+
+fn decode_digit(digit: u8, index: usize) -> Result<u8, HexError> {
+    match digit {
+        b'0'..=b'9' => Ok(digit - b'0'),
+        b'a'..=b'f' => Ok(digit - b'a' + 10),
+        b'A'..=b'F' => Ok(digit - b'A' + 10),
+        _ => Err(HexError::InvalidChar { index }),
+    }
+}
+
+#[cfg(any(feature = "serde", test))]
+fn decode_byte(s: &str, index: usize) -> Result<u8, HexError> {
+    let bytes = s.as_bytes();
+    Ok(decode_digit(bytes[index], index)? << 4 | decode_digit(bytes[index + 1], index + 1)?)
+}
+
+/// Iterates over bytes encoded in the string, which is copy-pasted by humans.
+///
+/// An optional `0x` or `0X` prefix is skipped and ASCII whitespace is ignored
+/// everywhere. Indices in errors refer to the original string.
+fn decode_lenient_iter(s: &str) -> impl Iterator<Item = Result<u8, HexError>> + '_ {
+    let trimmed = s.trim_start_matches(|c: char| c.is_ascii_whitespace());
+    let mut start = s.len() - trimmed.len();
+    if trimmed.starts_with("0x") || trimmed.starts_with("0X") {
+        start += 2;
+    }
+
+    let mut digits = s.bytes()
+        .enumerate()
+        .skip(start)
+        .filter(|&(_, digit)| !digit.is_ascii_whitespace());
+    std::iter::from_fn(move || {
+        let (high_index, high) = digits.next()?;
+        let byte = match digits.next() {
+            Some((low_index, low)) => decode_digit(high, high_index).and_then(|high| Ok(high << 4 | decode_digit(low, low_index)?)),
+            None => Err(HexError::OddLength),
+        };
+        Some(byte)
+    })
+}
+
+/// Decodes hex string of any even length, skipping `0x` prefix and whitespace.
+#[cfg(feature = "std")]
+pub(crate) fn decode_lenient(s: &str) -> Result<Vec<u8>, HexError> {
+    decode_lenient_iter(s).collect()
+}
+
+/// Decodes hex string of any even length.
+#[cfg(any(feature = "serde", test))]
+pub(crate) fn decode(s: &str) -> Result<Vec<u8>, HexError> {
+    if !s.len().is_multiple_of(2) {
+        return Err(HexError::OddLength);
+    }
+
+    (0..s.len()).step_by(2).map(|index| decode_byte(s, index)).collect()
+}
+
+/// Decodes hex string which must fill the whole buffer, skipping `0x` prefix and whitespace.
+pub(crate) fn decode_lenient_to_slice(s: &str, buf: &mut [u8]) -> Result<(), HexError> {
+    let mut len = 0;
+    for byte in decode_lenient_iter(s) {
+        let byte = byte?;
+        if let Some(slot) = buf.get_mut(len) {
+            *slot = byte;
+        }
+        len += 1;
+    }
+
+    if len != buf.len() {
+        return Err(HexError::InvalidLength { expected: buf.len() * 2, actual: len * 2 });
+    }
+    Ok(())
+}
+
+/// Encodes the bytes as lowercase hex.
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Writes the bytes as lowercase hex.
+pub(crate) fn write<'a, I: IntoIterator<Item = &'a u8>>(f: &mut fmt::Formatter, bytes: I) -> fmt::Result {
+    for byte in bytes {
+        write!(f, "{:02x}", byte)?;
+    }
+    Ok(())
+}
+
+/// Displays a few lines of hex dump around given offset, marking the byte at it.
+///
+/// Intended for debugging, the offset is usually taken from `ParseError::offset`.
+/// An offset equal to the length of the data marks the position after the end.
+///
+/// ```text
+/// 00000000  01 00 00 00 01 6d bd db  08 5b 1d 8a f7 51 84 f0
+///                                                 ^^
+/// ```
+pub struct HexDump<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> HexDump<'a> {
+    /// Number of bytes on each line.
+    const LINE_LEN: usize = 16;
+    /// Number of lines displayed before and after the line with the offset.
+    const CONTEXT_LINES: usize = 1;
+
+    /// Creates the dump of `data` marking the byte at `offset`.
+    pub fn new(data: &'a [u8], offset: usize) -> Self {
+        HexDump {
+            data,
+            offset,
+        }
+    }
+}
+
+impl<'a> fmt::Display for HexDump<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let offset_line = self.offset / Self::LINE_LEN;
+        let first_line = offset_line.saturating_sub(Self::CONTEXT_LINES);
+        let last_line = offset_line + Self::CONTEXT_LINES;
+
+        for line in first_line..=last_line {
+            let start = line * Self::LINE_LEN;
+            if start >= self.data.len() && line != offset_line {
+                break;
+            }
+            let end = cmp::min(start + Self::LINE_LEN, self.data.len());
+            let bytes = self.data.get(start..end).unwrap_or(&[]);
+
+            write!(f, "{:08x} ", start)?;
+            for (i, byte) in bytes.iter().enumerate() {
+                // Extra space separates each half of the line.
+                if i == Self::LINE_LEN / 2 {
+                    f.write_str(" ")?;
+                }
+                write!(f, " {:02x}", byte)?;
+            }
+            writeln!(f)?;
+
+            if line == offset_line {
+                let column = self.offset % Self::LINE_LEN;
+                let gap = if column >= Self::LINE_LEN / 2 { 1 } else { 0 };
+                writeln!(f, "{:width$}^^", "", width = 10 + column * 3 + gap)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_four(s: &str) -> Result<[u8; 4], HexError> {
+        let mut buf = [0; 4];
+        decode_lenient_to_slice(s, &mut buf).map(|()| buf)
+    }
+
+    #[test]
+    fn lenient() {
+        let expected = Ok([0x01, 0xab, 0xcd, 0xef]);
+        assert_eq!(decode_four("01abcdef"), expected);
+        assert_eq!(decode_four("0x01ABcdEF"), expected);
+        assert_eq!(decode_four("0X01abcdef"), expected);
+        assert_eq!(decode_four("  0x01 ab\tcd ef\n"), expected);
+        assert_eq!(decode_four("01\r\nab\r\ncdef\r\n"), expected);
+        // whitespace may even split a byte
+        assert_eq!(decode_four("01 abc def"), expected);
+    }
+
+    #[test]
+    fn lenient_errors() {
+        assert_eq!(decode_four("0x01abcde"), Err(HexError::OddLength));
+        assert_eq!(decode_four("01 abc de"), Err(HexError::OddLength));
+        assert_eq!(decode_four("01abcdeg"), Err(HexError::InvalidChar { index: 7 }));
+        // the prefix is only skipped at the beginning
+        assert_eq!(decode_four("010x0203"), Err(HexError::InvalidChar { index: 3 }));
+        assert_eq!(decode_four("0x01abcd"), Err(HexError::InvalidLength { expected: 8, actual: 6 }));
+    }
+
+    #[test]
+    fn hex_dump_window() {
+        let data = (0..40).collect::<Vec<u8>>();
+        let dump = |offset| format!("{}", HexDump::new(&data, offset));
+
+        // no line before the first one
+        assert_eq!(dump(0), concat!(
+            "00000000  00 01 02 03 04 05 06 07  08 09 0a 0b 0c 0d 0e 0f\n",
+            "          ^^\n",
+            "00000010  10 11 12 13 14 15 16 17  18 19 1a 1b 1c 1d 1e 1f\n",
+        ));
+        assert_eq!(dump(20), concat!(
+            "00000000  00 01 02 03 04 05 06 07  08 09 0a 0b 0c 0d 0e 0f\n",
+            "00000010  10 11 12 13 14 15 16 17  18 19 1a 1b 1c 1d 1e 1f\n",
+            "                      ^^\n",
+            "00000020  20 21 22 23 24 25 26 27\n",
+        ));
+        assert_eq!(dump(39), concat!(
+            "00000010  10 11 12 13 14 15 16 17  18 19 1a 1b 1c 1d 1e 1f\n",
+            "00000020  20 21 22 23 24 25 26 27\n",
+            "                               ^^\n",
+        ));
+        // after the end, where the parsing stops on truncated data
+        assert_eq!(dump(40), concat!(
+            "00000010  10 11 12 13 14 15 16 17  18 19 1a 1b 1c 1d 1e 1f\n",
+            "00000020  20 21 22 23 24 25 26 27\n",
+            "                                   ^^\n",
+        ));
+    }
+}
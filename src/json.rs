@@ -0,0 +1,345 @@
+//! JSON representation of transactions in the format of Bitcoin Core.
+
+use serde_json::{Map, Number, Value};
+use crate::amount::Amount;
+use crate::hash::{Hash256, Hash256Engine};
+use crate::hex;
+use crate::script::{Script, ScriptType, Instruction};
+use crate::transaction::{TxInput, TxOutput, Transaction};
+use crate::witness::Witness;
+
+/// Name of the script type used by Bitcoin Core.
+fn type_name(script_type: ScriptType) -> &'static str {
+    match script_type {
+        ScriptType::P2pk => "pubkey",
+        ScriptType::P2pkh => "pubkeyhash",
+        ScriptType::P2sh => "scripthash",
+        ScriptType::P2wpkh => "witness_v0_keyhash",
+        ScriptType::P2wsh => "witness_v0_scripthash",
+        ScriptType::OpReturn => "nulldata",
+        ScriptType::NonStandard => "nonstandard",
+    }
+}
+
+/// Formats the amount in BTC with exactly 8 decimal places.
+///
+/// The number is created from string, so it's exact and keeps the trailing
+/// zeros.
+fn btc_value(amount: Amount) -> Value {
+    let value = format!("{}.{:08}", amount.0 / Amount::ONE_BTC.0, amount.0 % Amount::ONE_BTC.0);
+    Value::Number(value.parse::<Number>().expect("formatted amount is a valid number"))
+}
+
+/// Interprets the pushed data as a number, same as `CScriptNum` in Bitcoin Core.
+///
+/// The number is little endian with the highest bit of the last byte being the sign.
+fn script_num(data: &[u8]) -> i64 {
+    let value = data.iter().rev().fold(0, |value, &byte| value << 8 | i64::from(byte));
+    match data.last() {
+        Some(&last) if last & 0x80 != 0 => -(value & !(0x80 << (8 * (data.len() - 1)))),
+        _ => value,
+    }
+}
+
+/// Checks whether the data is DER-encoded signature followed by sighash type.
+///
+/// This follows `IsValidSignatureEncoding` of Bitcoin Core (BIP66).
+fn is_der_signature(sig: &[u8]) -> bool {
+    if sig.len() < 9 || sig.len() > 73 || sig[0] != 0x30 || sig[1] as usize != sig.len() - 3 {
+        return false;
+    }
+
+    let len_r = sig[3] as usize;
+    if 5 + len_r >= sig.len() {
+        return false;
+    }
+    let len_s = sig[5 + len_r] as usize;
+    if len_r + len_s + 7 != sig.len() {
+        return false;
+    }
+
+    // Both are positive integers without unnecessary leading zeros.
+    let r = &sig[4..4 + len_r];
+    let s = &sig[6 + len_r..6 + len_r + len_s];
+    sig[2] == 0x02 && sig[4 + len_r] == 0x02 && [r, s].iter().all(|int| match **int {
+        [] => false,
+        [first, ..] if first & 0x80 != 0 => false,
+        [0x00, second, ..] => second & 0x80 != 0,
+        _ => true,
+    })
+}
+
+/// Name of the sighash type which Bitcoin Core shows in brackets after signatures.
+fn sighash_name(sighash_type: u8) -> Option<&'static str> {
+    match sighash_type {
+        0x01 => Some("ALL"),
+        0x81 => Some("ALL|ANYONECANPAY"),
+        0x02 => Some("NONE"),
+        0x82 => Some("NONE|ANYONECANPAY"),
+        0x03 => Some("SINGLE"),
+        0x83 => Some("SINGLE|ANYONECANPAY"),
+        _ => None,
+    }
+}
+
+/// Disassembles the script the same way as `ScriptToAsmStr` in Bitcoin Core.
+///
+/// Unlike `Display` of `Script`, small integers and pushes of up to 4 bytes
+/// are shown as numbers. With `decode_sighash` the sighash type of signatures
+/// is shown in brackets, as Core does for input scripts.
+fn script_asm(script: &Script, decode_sighash: bool) -> String {
+    let decode_sighash = decode_sighash && !script.is_provably_unspendable();
+    let mut asm = String::new();
+    for instruction in script.instructions() {
+        if !asm.is_empty() {
+            asm.push(' ');
+        }
+
+        match instruction {
+            Ok(Instruction::PushBytes(data)) if data.len() <= 4 => asm.push_str(&script_num(data).to_string()),
+            Ok(Instruction::PushBytes(data)) => {
+                let (&sighash_type, signature) = data.split_last().expect("data is not empty");
+                match sighash_name(sighash_type) {
+                    Some(name) if decode_sighash && is_der_signature(data) => {
+                        asm.push_str(&hex::encode(signature));
+                        asm.push('[');
+                        asm.push_str(name);
+                        asm.push(']');
+                    },
+                    _ => asm.push_str(&hex::encode(data)),
+                }
+            },
+            Ok(Instruction::Op(opcode)) => match u8::from(opcode) {
+                // OP_1NEGATE
+                0x4f => asm.push_str("-1"),
+                // OP_1 ..= OP_16
+                opcode @ 0x51..=0x60 => asm.push_str(&(opcode - 0x50).to_string()),
+                _ => asm.push_str(opcode.name()),
+            },
+            Err(_) => {
+                asm.push_str("[error]");
+                break;
+            },
+        }
+    }
+    asm
+}
+
+fn script_json(script: &Script, decode_sighash: bool) -> Map<String, Value> {
+    let mut map = Map::new();
+    map.insert("asm".to_owned(), script_asm(script, decode_sighash).into());
+    map.insert("hex".to_owned(), script.to_hex().into());
+    map
+}
+
+fn input_json(input: &TxInput, witness: Option<&Witness>, is_coinbase: bool) -> Value {
+    let mut map = Map::new();
+    if is_coinbase {
+        map.insert("coinbase".to_owned(), input.sig_script.to_hex().into());
+    } else {
+        map.insert("txid".to_owned(), input.outpoint.txid.to_string().into());
+        map.insert("vout".to_owned(), input.outpoint.index.into());
+        map.insert("scriptSig".to_owned(), script_json(&input.sig_script, true).into());
+    }
+    if let Some(witness) = witness.filter(|witness| !witness.0.is_empty()) {
+        let items = witness.0.iter().map(|item| Value::from(hex::encode(item))).collect::<Vec<_>>();
+        map.insert("txinwitness".to_owned(), items.into());
+    }
+    map.insert("sequence".to_owned(), input.sequence.into());
+    map.into()
+}
+
+fn output_json(output: &TxOutput, n: usize) -> Value {
+    let mut script_pubkey = script_json(&output.verify_script, false);
+    script_pubkey.insert("type".to_owned(), type_name(output.verify_script.script_type()).into());
+
+    let mut map = Map::new();
+    map.insert("value".to_owned(), btc_value(output.satoshis));
+    map.insert("n".to_owned(), n.into());
+    map.insert("scriptPubKey".to_owned(), script_pubkey.into());
+    map.into()
+}
+
+/// Hashes the transaction including witness, which Bitcoin Core shows as `hash`.
+///
+/// Unlike `Transaction::wtxid` this isn't zero for coinbase.
+fn witness_hash(transaction: &Transaction) -> Hash256 {
+    let mut engine = Hash256Engine::new();
+    transaction.serialize(&mut engine).expect("hashing never fails");
+    engine.finalize()
+}
+
+impl Transaction {
+    /// Returns JSON value with the same structure `bitcoin-cli decoderawtransaction` produces.
+    ///
+    /// Addresses and descriptors are not included since the network is unknown.
+    pub fn to_decoded_json(&self) -> Value {
+        let is_coinbase = self.is_coinbase();
+        let vin = self.inputs
+            .iter()
+            .enumerate()
+            .map(|(i, input)| input_json(input, self.witnesses.get(i), is_coinbase))
+            .collect::<Vec<_>>();
+        let vout = self.outputs
+            .iter()
+            .enumerate()
+            .map(|(n, output)| output_json(output, n))
+            .collect::<Vec<_>>();
+
+        let mut map = Map::new();
+        map.insert("txid".to_owned(), self.txid().to_string().into());
+        map.insert("hash".to_owned(), witness_hash(self).to_string().into());
+        map.insert("version".to_owned(), self.version.into());
+        map.insert("size".to_owned(), self.total_size().into());
+        map.insert("vsize".to_owned(), self.vsize().into());
+        map.insert("weight".to_owned(), self.weight().into());
+        map.insert("locktime".to_owned(), self.lock_time.into());
+        map.insert("vin".to_owned(), vin.into());
+        map.insert("vout".to_owned(), vout.into());
+        map.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_vectors::*;
+
+    /// Output of `bitcoin-cli decoderawtransaction` for `TX_170`, without addresses and descriptors.
+    const TX_170_JSON: &str = r#"{
+        "txid": "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16",
+        "hash": "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16",
+        "version": 1,
+        "size": 275,
+        "vsize": 275,
+        "weight": 1100,
+        "locktime": 0,
+        "vin": [
+            {
+                "txid": "0437cd7f8525ceed2324359c2d0ba26006d92d856a9c20fa0241106ee5a597c9",
+                "vout": 0,
+                "scriptSig": {
+                    "asm": "304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d09[ALL]",
+                    "hex": "47304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901"
+                },
+                "sequence": 4294967295
+            }
+        ],
+        "vout": [
+            {
+                "value": 10.00000000,
+                "n": 0,
+                "scriptPubKey": {
+                    "asm": "04ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84c OP_CHECKSIG",
+                    "hex": "4104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac",
+                    "type": "pubkey"
+                }
+            },
+            {
+                "value": 40.00000000,
+                "n": 1,
+                "scriptPubKey": {
+                    "asm": "0411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3 OP_CHECKSIG",
+                    "hex": "410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac",
+                    "type": "pubkey"
+                }
+            }
+        ]
+    }"#;
+
+    /// Output of `bitcoin-cli decoderawtransaction` for `BIP143_P2WPKH`, without addresses and descriptors.
+    const BIP143_P2WPKH_JSON: &str = r#"{
+        "txid": "e8151a2af31c368a35053ddd4bdb285a8595c769a3ad83e0fa02314a602d4609",
+        "hash": "c36c38370907df2324d9ce9d149d191192f338b37665a82e78e76a12c909b762",
+        "version": 1,
+        "size": 343,
+        "vsize": 261,
+        "weight": 1042,
+        "locktime": 17,
+        "vin": [
+            {
+                "txid": "9f96ade4b41d5433f4eda31e1738ec2b36f6e7d1420d94a6af99801a88f7f7ff",
+                "vout": 0,
+                "scriptSig": {
+                    "asm": "30450221008b9d1dc26ba6a9cb62127b02742fa9d754cd3bebf337f7a55d114c8e5cdd30be022040529b194ba3f9281a99f2b1c0a19c0489bc22ede944ccf4ecbab4cc618ef3ed[ALL]",
+                    "hex": "4830450221008b9d1dc26ba6a9cb62127b02742fa9d754cd3bebf337f7a55d114c8e5cdd30be022040529b194ba3f9281a99f2b1c0a19c0489bc22ede944ccf4ecbab4cc618ef3ed01"
+                },
+                "sequence": 4294967278
+            },
+            {
+                "txid": "8ac60eb9575db5b2d987e29f301b5b819ea83a5c6579d282d189cc04b8e151ef",
+                "vout": 1,
+                "scriptSig": {
+                    "asm": "",
+                    "hex": ""
+                },
+                "txinwitness": [
+                    "304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a0220573a954c4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee01",
+                    "025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeee6357"
+                ],
+                "sequence": 4294967295
+            }
+        ],
+        "vout": [
+            {
+                "value": 1.12340000,
+                "n": 0,
+                "scriptPubKey": {
+                    "asm": "OP_DUP OP_HASH160 8280b37df378db99f66f85c95a783a76ac7a6d59 OP_EQUALVERIFY OP_CHECKSIG",
+                    "hex": "76a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac",
+                    "type": "pubkeyhash"
+                }
+            },
+            {
+                "value": 2.23450000,
+                "n": 1,
+                "scriptPubKey": {
+                    "asm": "OP_DUP OP_HASH160 3bde42dbee7e4dbe6a21b2d50ce2f0167faa8159 OP_EQUALVERIFY OP_CHECKSIG",
+                    "hex": "76a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac",
+                    "type": "pubkeyhash"
+                }
+            }
+        ]
+    }"#;
+
+    fn assert_json(transaction_hex: &str, expected: &str) {
+        let transaction = Transaction::from_hex(transaction_hex).unwrap();
+        let expected = serde_json::from_str::<Value>(expected).unwrap();
+        // Comparing strings checks also the order of fields and the formatting of values.
+        assert_eq!(transaction.to_decoded_json().to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn legacy_matches_core() {
+        assert_json(TX_170, TX_170_JSON);
+    }
+
+    #[test]
+    fn segwit_matches_core() {
+        assert_json(BIP143_P2WPKH, BIP143_P2WPKH_JSON);
+    }
+
+    #[test]
+    fn asm_numbers() {
+        let script = Script(hex::decode("00514f6002ff000181048180008001800100").unwrap());
+        assert_eq!(script_asm(&script, false), "0 1 -1 16 255 -1 -32897 0 0");
+    }
+
+    #[test]
+    fn asm_sighash_only_in_input_scripts() {
+        let transaction = Transaction::from_hex(TX_170).unwrap();
+        let script = &transaction.inputs[0].sig_script;
+        assert!(script_asm(script, true).ends_with("09[ALL]"));
+        assert!(script_asm(script, false).ends_with("0901"));
+
+        // OP_RETURN data looking like a signature isn't decoded
+        let mut op_return = vec![0x6a];
+        op_return.extend_from_slice(&script.0);
+        assert!(script_asm(&Script(op_return), true).ends_with("0901"));
+    }
+
+    #[test]
+    fn asm_error() {
+        assert_eq!(script_asm(&Script(vec![0x76, 0x4c]), false), "OP_DUP [error]");
+    }
+}
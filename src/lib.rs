@@ -0,0 +1,106 @@
+//! Tutorial parser of Bitcoin data structures.
+//!
+//! Without the default `std` feature, only the zero-copy parsing from byte
+//! slices and methods not needing I/O are available.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+// Makes `std::` paths work for items that are in `core`.
+#[cfg(not(feature = "std"))]
+extern crate core as std;
+
+extern crate byteorder;
+extern crate sha2;
+extern crate bs58;
+extern crate bech32;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "json")]
+extern crate serde_json;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+#[cfg(feature = "wasm")]
+extern crate js_sys;
+#[cfg(feature = "rust-bitcoin")]
+extern crate bitcoin;
+
+/// Allocated types which are not in prelude without `std`.
+mod prelude {
+    #[cfg(feature = "std")]
+    pub use std::vec::Vec;
+    #[cfg(feature = "std")]
+    pub use std::string::{String, ToString};
+
+    #[cfg(not(feature = "std"))]
+    pub use alloc::vec::Vec;
+    #[cfg(not(feature = "std"))]
+    pub use alloc::string::{String, ToString};
+    #[cfg(not(feature = "std"))]
+    pub use alloc::boxed::Box;
+}
+
+mod error;
+mod hex;
+mod limits;
+mod amount;
+mod cursor;
+mod varint;
+mod hash;
+mod opcodes;
+mod script;
+mod address;
+mod witness;
+mod transaction;
+mod borrowed;
+mod block;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "std")]
+mod network;
+#[cfg(feature = "std")]
+mod compact_block;
+#[cfg(feature = "std")]
+mod sighash;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "rust-bitcoin")]
+mod bitcoin_impls;
+#[cfg(test)]
+mod test_vectors;
+
+pub use error::ParseError;
+pub use hex::{HexError, HexDump};
+pub use limits::{Limits, SizeHint};
+pub use amount::Amount;
+#[cfg(feature = "std")]
+pub use varint::{deserialize_varint, deserialize_varint_canonical, serialize_varint};
+pub use varint::{VarInt, varint_len};
+#[cfg(feature = "tokio")]
+pub use varint::deserialize_varint_async;
+pub use hash::{Hash256, Hash160, Hash256Engine, hash256, hash160};
+pub use opcodes::Opcode;
+pub use script::{Script, ScriptType, Instructions, Instruction, ScriptError, ScriptBuilder, MultisigInfo};
+pub use address::Network;
+pub use witness::{Witness, TaprootSpend, ControlBlock};
+pub use transaction::{Outpoint, TxInput, TxOutput, Transaction, TransactionBuilder, TransactionHeader, StreamedTransaction, LockTime, InputInfo, OutputInfo};
+pub use borrowed::{ScriptRef, WitnessRef, TxInputRef, TxOutputRef, TransactionRef};
+pub use block::{BlockHeader, Block, BlockError};
+#[cfg(feature = "std")]
+pub use reader::{TransactionReader, TransactionDecoder, BlockFileReader, LimitedReader, read_array};
+#[cfg(feature = "std")]
+pub use network::{MessageHeader, NetworkMessage, P2PMessage, InvType, InvItem, Inventory, Headers, BlockLocator, VarStr, NetAddr, TimestampedNetAddr, AddrMessage, VersionMessage};
+#[cfg(feature = "std")]
+pub use compact_block::{CompactBlock, PrefilledTransaction};
+#[cfg(feature = "std")]
+pub use sighash::{SIGHASH_ALL, SIGHASH_NONE, SIGHASH_SINGLE, SIGHASH_ANYONECANPAY};
+#[cfg(feature = "wasm")]
+pub use wasm::{parse_transaction_hex, transaction_txid};
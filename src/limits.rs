@@ -0,0 +1,144 @@
+use std::cmp;
+use std::convert::TryFrom;
+use crate::error::ParseError;
+use crate::amount::Amount;
+
+/// Sanity limits checked when deserializing.
+///
+/// They protect against corrupted inputs, but non-standard test vectors may
+/// need to exceed them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum length of a script in bytes
+    pub max_script_len: u64,
+    /// Maximum number of inputs of a transaction
+    pub max_inputs: u64,
+    /// Maximum number of outputs of a transaction
+    pub max_outputs: u64,
+    /// Maximum number of bytes read when deserializing a transaction from a reader
+    pub max_transaction_size: u64,
+    /// Maximum value of a single output
+    pub max_output_value: Amount,
+}
+
+impl Limits {
+    /// Limits derived from consensus rules.
+    pub fn consensus() -> Self {
+        Limits {
+            // This is consensus rule.
+            max_script_len: 10_000,
+            // Since block can contain only 1M of bytes and each input/output
+            // has more than one byte, more can't happen for valid transaction.
+            max_inputs: 1_000_000,
+            max_outputs: 1_000_000,
+            // Weight of a transaction is at least its size, and block weight
+            // is limited to 4M. (BIP141)
+            max_transaction_size: 4_000_000,
+            // Values above `MAX_MONEY` are invalid too, but arbitrary test data may contain them.
+            max_output_value: Amount(u64::MAX),
+        }
+    }
+
+    /// Consensus limits also rejecting output values above `Amount::MAX_MONEY`.
+    ///
+    /// Such values are invalid, so they indicate corrupted data.
+    pub fn strict() -> Self {
+        Limits {
+            max_output_value: Amount::MAX_MONEY,
+            ..Limits::consensus()
+        }
+    }
+
+    /// No limits at all.
+    pub fn unlimited() -> Self {
+        Limits {
+            max_script_len: u64::MAX,
+            max_inputs: u64::MAX,
+            max_outputs: u64::MAX,
+            max_transaction_size: u64::MAX,
+            max_output_value: Amount(u64::MAX),
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits::consensus()
+    }
+}
+
+/// Expected numbers of elements, used to allocate memory upfront.
+///
+/// Lengths read from the data aren't trusted, so only a limited number of
+/// elements is allocated before they are actually parsed. The hint allows
+/// allocating more, but never more than the length read from the data. Zero
+/// means no hint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SizeHint {
+    /// Expected number of transactions in a block
+    pub transactions: u64,
+    /// Expected number of inputs of a transaction
+    pub inputs: u64,
+    /// Expected number of outputs of a transaction
+    pub outputs: u64,
+}
+
+/// Maximum number of elements allocated upfront based on untrusted length.
+const MAX_PREALLOCATION: u64 = 4096;
+
+/// Returns capacity to preallocate for `len` elements read from the input.
+///
+/// The length was not verified against the actual data yet, so the capacity is capped
+/// and the vector grows as the data is actually read.
+pub(crate) fn preallocation(len: u64) -> usize {
+    cmp::min(len, MAX_PREALLOCATION) as usize
+}
+
+/// Returns capacity to preallocate for `len` elements read from the input, expecting `hint` elements.
+#[cfg(feature = "std")]
+pub(crate) fn preallocation_hinted(len: u64, hint: u64) -> usize {
+    cmp::max(preallocation(len), cmp::min(len, hint) as usize)
+}
+
+/// Converts length or count read from the input to `usize`.
+///
+/// On 32-bit platforms the value may not fit, casting it would silently
+/// truncate it.
+pub(crate) fn checked_len(len: u64) -> Result<usize, ParseError> {
+    usize::try_from(len).map_err(|_| ParseError::LengthOverflow { len })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_len_fits_platform() {
+        assert_eq!(checked_len(u64::from(u32::MAX)).unwrap(), u32::MAX as usize);
+        #[cfg(target_pointer_width = "32")]
+        assert!(matches!(checked_len(u64::from(u32::MAX) + 1), Err(ParseError::LengthOverflow { len: 0x1_0000_0000 })));
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(checked_len(u64::MAX).unwrap(), usize::MAX);
+    }
+
+    #[test]
+    fn preallocation_is_capped() {
+        assert_eq!(preallocation(10), 10);
+        assert_eq!(preallocation(u64::MAX), MAX_PREALLOCATION as usize);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn huge_script_length() {
+        use crate::script::Script;
+
+        // length 2^32 followed by a single byte
+        let data = [0xff, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xaa];
+        let result = Script::deserialize_with_limits(&mut &data[..], &Limits::unlimited());
+        if cfg!(target_pointer_width = "32") {
+            assert!(matches!(result, Err(ParseError::LengthOverflow { len: 0x1_0000_0000 })));
+        } else {
+            assert!(matches!(result, Err(ParseError::UnexpectedEof)));
+        }
+    }
+}
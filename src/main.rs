@@ -1,159 +1,105 @@
-extern crate byteorder;
+extern crate btc_parse_tutor;
 
-use byteorder::{LE, ReadBytesExt};
+use btc_parse_tutor::Transaction;
+use std::env;
+use std::fs::File;
 use std::io;
-use std::io::{Read, ErrorKind};
+use std::io::Read;
+use std::path::PathBuf;
+use std::process;
+use std::str;
 
-/// Deserializes "varint" as defined by Bitcoin protocol.
-fn deserialize_varint<R: Read>(reader: &mut R) -> io::Result<u64> {
-    match reader.read_u8()? {
-        253 => reader.read_u16::<LE>().map(Into::into),
-        254 => reader.read_u32::<LE>().map(Into::into),
-        255 => reader.read_u64::<LE>().map(Into::into),
-        x   => Ok(x.into()),
-    }
-}
-
-/// Represent's Bitcoin script.
-struct Script(Vec<u8>);
-
-impl Script {
-    /// Deserializes the script from a reader.
-    fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
-        let len = deserialize_varint(reader)?;
-        // This is consensus rule, so theoretically no need to check it,
-        // but serves as a protection against corrupted inputs.
-        if len > 10_000 {
-            return Err(ErrorKind::InvalidData.into());
-        }
-
-        let mut reader = reader.by_ref().take(len);
-        let mut data = Vec::with_capacity(len as usize);
-
-        io::copy(&mut reader, &mut data)?;
-        Ok(Script(data))
-    }
-}
+const USAGE: &str = "Usage: btc_parse_tutor [--hex] [FILE]
 
-/// Represents 256 bit hash. (SHA256)
-struct Hash256([u8; 32]);
+Parses a transaction from FILE or standard input and prints its fields.
 
-impl Hash256 {
-    /// Deserializes the hash
-    fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
-        let mut buf = [0; 32];
-        reader.read_exact(&mut buf)?;
+Options:
+    --hex    treat the input as hex string instead of raw bytes";
 
-        Ok(Hash256(buf))
-    }
-}
-
-/// Defines "outpoint" - output of previous transaction being consumed.
-struct Outpoint {
-    /// ID of previous transaction
-    txid: Hash256,
-    /// Which output of the previous transaction is being consumed.
-    index: u32,
-}
-
-impl Outpoint {
-    /// Deserializes the outpoint from the blockchain data
-    fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
-        let txid = Hash256::deserialize(reader)?;
-        let index = reader.read_u32::<LE>()?;
-
-        Ok(Outpoint {
-            txid,
-            index,
-        })
-    }
+/// Command line arguments
+struct Args {
+    hex: bool,
+    path: Option<PathBuf>,
 }
 
-/// Contains data about single transaction input.
-struct TxInput {
-    outpoint: Outpoint,
-    sig_script: Script,
-    sequence: u32,
-}
-
-impl TxInput {
-    /// Deserializes the input from the blockchain data
-    fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
-        let outpoint = Outpoint::deserialize(reader)?;
-        let sig_script = Script::deserialize(reader)?;
-        let sequence = reader.read_u32::<LE>()?;
+impl Args {
+    fn parse() -> Option<Self> {
+        let mut hex = false;
+        let mut path = None;
+
+        for arg in env::args_os().skip(1) {
+            if arg == "--hex" {
+                hex = true;
+            } else if arg == "--help" || arg == "-h" || path.is_some() {
+                return None;
+            } else {
+                path = Some(arg.into());
+            }
+        }
 
-        Ok(TxInput {
-            outpoint,
-            sig_script,
-            sequence,
+        Some(Args {
+            hex,
+            path,
         })
     }
 }
 
-/// Contains data about single transaction output.
-struct TxOutput {
-    satoshis: u64,
-    verify_script: Script,
+fn read_input(path: Option<&PathBuf>) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    match path {
+        Some(path) => File::open(path)?.read_to_end(&mut data)?,
+        None => io::stdin().read_to_end(&mut data)?,
+    };
+    Ok(data)
 }
 
-impl TxOutput {
-    /// Deserializes the input from the blockchain data
-    fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
-        let satoshis = reader.read_u64::<LE>()?;
-        let verify_script = Script::deserialize(reader)?;
-
-        Ok(TxOutput {
-            satoshis,
-            verify_script
-        })
-    }
-}
+fn parse(data: &[u8], hex: bool) -> Result<Transaction, String> {
+    let result = if hex {
+        let hex = str::from_utf8(data).map_err(|_| "input is not valid UTF-8".to_owned())?;
+        Transaction::from_hex(hex)
+    } else {
+        Transaction::deserialize(&mut &*data)
+    };
 
-/// Contains data about single transaction
-struct Transaction {
-    version: u32,
-    inputs: Vec<TxInput>,
-    outputs: Vec<TxOutput>,
-    lock_time: u32,
+    result.map_err(|error| error.to_string())
 }
 
-impl Transaction {
-    fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
-        let version = reader.read_u32::<LE>()?;
-        let input_count = deserialize_varint(reader)?;
-
-        // Sanity check. Since block can contain only 1M of bytes and each input
-        // has more than one byte, this can't happen for valid transaction.
-        if input_count > 1_000_000 {
-            return Err(ErrorKind::InvalidData.into());
+fn print_transaction(tx: &Transaction) {
+    println!("txid: {}", tx.txid());
+    println!("version: {}", tx.version);
+    println!("inputs: {}", tx.inputs.len());
+    for (i, input) in tx.inputs.iter().enumerate() {
+        println!("  #{} outpoint: {}:{}", i, input.outpoint.txid, input.outpoint.index);
+        println!("     script: {}", input.sig_script.to_hex());
+        println!("     sequence: 0x{:08x}", input.sequence);
+        if let Some(witness) = tx.witnesses.get(i) {
+            println!("     witness items: {}", witness.0.len());
         }
-        let mut inputs = Vec::with_capacity(input_count as usize);
-        for _ in 0..input_count {
-            inputs.push(TxInput::deserialize(reader)?);
-        }
-
-        let output_count = deserialize_varint(reader)?;
-        // Sanity check. Since block can contain only 1M of bytes and each input
-        // has more than one byte, this can't happen for valid transaction.
-        if output_count > 1_000_000 {
-            return Err(ErrorKind::InvalidData.into());
-        }
-        let mut outputs = Vec::with_capacity(output_count as usize);
-        for _ in 0..output_count {
-            outputs.push(TxOutput::deserialize(reader)?);
-        }
-        let lock_time = reader.read_u32::<LE>()?;
-
-        Ok(Transaction {
-            version,
-            inputs,
-            outputs,
-            lock_time,
-        })
     }
+    println!("outputs: {}", tx.outputs.len());
+    for (i, output) in tx.outputs.iter().enumerate() {
+        println!("  #{} satoshis: {}", i, output.satoshis.to_sat());
+        println!("     script: {}", output.verify_script.to_hex());
+    }
+    println!("lock_time: {}", tx.lock_time);
 }
 
 fn main() {
-    println!("Hello, world!");
+    let args = Args::parse().unwrap_or_else(|| {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    });
+
+    let data = read_input(args.path.as_ref()).unwrap_or_else(|error| {
+        eprintln!("Failed to read input: {}", error);
+        process::exit(1);
+    });
+
+    match parse(&data, args.hex) {
+        Ok(tx) => print_transaction(&tx),
+        Err(error) => {
+            eprintln!("Failed to parse transaction: {}", error);
+            process::exit(1);
+        },
+    }
 }
@@ -0,0 +1,906 @@
+//! Messages of Bitcoin P2P network protocol.
+
+use byteorder::{BE, LE, ReadBytesExt, WriteBytesExt};
+use std::fmt;
+use std::io;
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv6Addr};
+use crate::error::ParseError;
+use crate::limits;
+use crate::reader::read_array;
+use crate::varint::{deserialize_varint, serialize_varint};
+use crate::hash::Hash256;
+use crate::block::{Block, BlockHeader};
+use crate::compact_block::CompactBlock;
+use crate::transaction::Transaction;
+
+/// Maximum size of message payload accepted by Bitcoin Core.
+const MAX_PAYLOAD_LEN: u32 = 32 * 1024 * 1024;
+
+/// Header preceding every message in P2P protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageHeader {
+    /// Identifies the network, stored as little endian
+    pub magic: u32,
+    /// Name of the message padded with zeros
+    pub command: [u8; 12],
+    /// Length of the payload
+    pub length: u32,
+    /// First four bytes of double SHA256 of the payload
+    pub checksum: [u8; 4],
+}
+
+impl MessageHeader {
+    /// Deserializes the header from network data
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let magic = reader.read_u32::<LE>()?;
+        let command = read_array(reader)?;
+        let length = reader.read_u32::<LE>()?;
+        let checksum = read_array(reader)?;
+
+        Ok(MessageHeader {
+            magic,
+            command,
+            length,
+            checksum,
+        })
+    }
+
+    /// Serializes the header the same way it's sent over network
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_u32::<LE>(self.magic)?;
+        writer.write_all(&self.command)?;
+        writer.write_u32::<LE>(self.length)?;
+        writer.write_all(&self.checksum)
+    }
+
+    /// Returns the name of the command without zero padding.
+    pub fn command_name(&self) -> &[u8] {
+        let len = self.command.iter().position(|&byte| byte == 0).unwrap_or(self.command.len());
+        &self.command[..len]
+    }
+
+    /// Reads the payload described by the header, verifying its checksum.
+    pub fn read_payload<R: Read>(&self, reader: &mut R) -> Result<Vec<u8>, ParseError> {
+        if self.length > MAX_PAYLOAD_LEN {
+            return Err(ParseError::PayloadTooLong { len: self.length });
+        }
+
+        // The length is not trusted until the data arrives, so it's not preallocated.
+        let len = u64::from(self.length);
+        let mut payload = Vec::with_capacity(limits::preallocation(len));
+        reader.take(len).read_to_end(&mut payload)?;
+        if payload.len() as u64 != len {
+            return Err(ParseError::UnexpectedEof);
+        }
+        if checksum(&payload) != self.checksum {
+            return Err(ParseError::ChecksumMismatch);
+        }
+
+        Ok(payload)
+    }
+}
+
+/// Computes checksum of the payload.
+fn checksum(payload: &[u8]) -> [u8; 4] {
+    let hash = Hash256::double_sha256(payload);
+    [hash.0[0], hash.0[1], hash.0[2], hash.0[3]]
+}
+
+/// P2P message carrying a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkMessage {
+    /// Identifies the network, stored as little endian
+    pub magic: u32,
+    pub transaction: Transaction,
+}
+
+impl NetworkMessage {
+    /// Deserializes `tx` message, verifying its command, length and checksum.
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let header = MessageHeader::deserialize(reader)?;
+        if header.command_name() != b"tx" {
+            return Err(ParseError::UnexpectedCommand { command: header.command });
+        }
+
+        let payload = header.read_payload(reader)?;
+        let mut payload_reader = &*payload;
+        let transaction = Transaction::deserialize(&mut payload_reader)?;
+        if !payload_reader.is_empty() {
+            return Err(ParseError::LengthMismatch {
+                expected: payload.len(),
+                actual: payload.len() - payload_reader.len(),
+            });
+        }
+
+        Ok(NetworkMessage {
+            magic: header.magic,
+            transaction,
+        })
+    }
+
+    /// Serializes the message including the header
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut payload = Vec::new();
+        self.transaction.serialize(&mut payload)?;
+
+        write_message(writer, self.magic, b"tx", &payload)
+    }
+}
+
+/// Writes the message header followed by the payload.
+fn write_message<W: Write>(writer: &mut W, magic: u32, command_name: &[u8], payload: &[u8]) -> io::Result<()> {
+    let mut command = [0; 12];
+    command[..command_name.len()].copy_from_slice(command_name);
+    let header = MessageHeader {
+        magic,
+        command,
+        length: payload.len() as u32,
+        checksum: checksum(payload),
+    };
+    header.serialize(writer)?;
+    writer.write_all(payload)
+}
+
+/// P2P message with payload parsed according to its command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum P2PMessage {
+    Version(VersionMessage),
+    Verack,
+    Addr(AddrMessage),
+    /// `ping` with its nonce
+    Ping(u64),
+    /// `pong` with the nonce of the `ping` it answers
+    Pong(u64),
+    /// `feefilter` with minimal fee rate in satoshis per kB (BIP133)
+    FeeFilter(u64),
+    /// `sendheaders` (BIP130)
+    SendHeaders,
+    Tx(Transaction),
+    Block(Block),
+    Headers(Headers),
+    GetBlocks(BlockLocator),
+    GetHeaders(BlockLocator),
+    Inv(Inventory),
+    GetData(Inventory),
+    NotFound(Inventory),
+    /// `cmpctblock` (BIP152)
+    CompactBlock(CompactBlock),
+    /// Message with command not known to this library, the payload is kept as is
+    Unknown { command: [u8; 12], payload: Vec<u8> },
+}
+
+impl P2PMessage {
+    /// Deserializes the message, verifying its length and checksum.
+    ///
+    /// The magic is not checked, use `MessageHeader` and `from_payload` if it's needed.
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let header = MessageHeader::deserialize(reader)?;
+        let payload = header.read_payload(reader)?;
+        P2PMessage::from_payload(&header.command, payload)
+    }
+
+    /// Parses the payload according to the zero-padded command from message header.
+    pub fn from_payload(command: &[u8; 12], payload: Vec<u8>) -> Result<Self, ParseError> {
+        let len = command.iter().position(|&byte| byte == 0).unwrap_or(command.len());
+        let mut reader = &*payload;
+        let message = match &command[..len] {
+            b"version" => P2PMessage::Version(VersionMessage::deserialize(&mut reader)?),
+            b"verack" => P2PMessage::Verack,
+            b"addr" => P2PMessage::Addr(AddrMessage::deserialize(&mut reader)?),
+            b"ping" => P2PMessage::Ping(reader.read_u64::<LE>()?),
+            b"pong" => P2PMessage::Pong(reader.read_u64::<LE>()?),
+            b"feefilter" => P2PMessage::FeeFilter(reader.read_u64::<LE>()?),
+            b"sendheaders" => P2PMessage::SendHeaders,
+            b"tx" => P2PMessage::Tx(Transaction::deserialize(&mut reader)?),
+            b"block" => P2PMessage::Block(Block::deserialize(&mut reader)?),
+            b"headers" => P2PMessage::Headers(Headers::deserialize(&mut reader)?),
+            b"getblocks" => P2PMessage::GetBlocks(BlockLocator::deserialize(&mut reader)?),
+            b"getheaders" => P2PMessage::GetHeaders(BlockLocator::deserialize(&mut reader)?),
+            b"inv" => P2PMessage::Inv(Inventory::deserialize(&mut reader)?),
+            b"getdata" => P2PMessage::GetData(Inventory::deserialize(&mut reader)?),
+            b"notfound" => P2PMessage::NotFound(Inventory::deserialize(&mut reader)?),
+            b"cmpctblock" => P2PMessage::CompactBlock(CompactBlock::deserialize(&mut reader)?),
+            _ => return Ok(P2PMessage::Unknown { command: *command, payload }),
+        };
+
+        if !reader.is_empty() {
+            return Err(ParseError::LengthMismatch {
+                expected: payload.len(),
+                actual: payload.len() - reader.len(),
+            });
+        }
+        Ok(message)
+    }
+
+    /// Returns the name of the command of the message without zero padding.
+    pub fn command_name(&self) -> &[u8] {
+        match *self {
+            P2PMessage::Version(_) => b"version",
+            P2PMessage::Verack => b"verack",
+            P2PMessage::Addr(_) => b"addr",
+            P2PMessage::Ping(_) => b"ping",
+            P2PMessage::Pong(_) => b"pong",
+            P2PMessage::FeeFilter(_) => b"feefilter",
+            P2PMessage::SendHeaders => b"sendheaders",
+            P2PMessage::Tx(_) => b"tx",
+            P2PMessage::Block(_) => b"block",
+            P2PMessage::Headers(_) => b"headers",
+            P2PMessage::GetBlocks(_) => b"getblocks",
+            P2PMessage::GetHeaders(_) => b"getheaders",
+            P2PMessage::Inv(_) => b"inv",
+            P2PMessage::GetData(_) => b"getdata",
+            P2PMessage::NotFound(_) => b"notfound",
+            P2PMessage::CompactBlock(_) => b"cmpctblock",
+            P2PMessage::Unknown { ref command, .. } => {
+                let len = command.iter().position(|&byte| byte == 0).unwrap_or(command.len());
+                &command[..len]
+            },
+        }
+    }
+
+    /// Serializes the payload of the message, without the header.
+    pub fn serialize_payload<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match *self {
+            P2PMessage::Version(ref version) => version.serialize(writer),
+            P2PMessage::Verack | P2PMessage::SendHeaders => Ok(()),
+            P2PMessage::Addr(ref addresses) => addresses.serialize(writer),
+            P2PMessage::Ping(nonce) | P2PMessage::Pong(nonce) => writer.write_u64::<LE>(nonce),
+            P2PMessage::FeeFilter(fee_rate) => writer.write_u64::<LE>(fee_rate),
+            P2PMessage::Tx(ref transaction) => transaction.serialize(writer),
+            P2PMessage::Block(ref block) => block.serialize(writer),
+            P2PMessage::Headers(ref headers) => headers.serialize(writer),
+            P2PMessage::GetBlocks(ref locator) | P2PMessage::GetHeaders(ref locator) => locator.serialize(writer),
+            P2PMessage::Inv(ref inventory) | P2PMessage::GetData(ref inventory) | P2PMessage::NotFound(ref inventory) => inventory.serialize(writer),
+            P2PMessage::CompactBlock(ref block) => block.serialize(writer),
+            P2PMessage::Unknown { ref payload, .. } => writer.write_all(payload),
+        }
+    }
+
+    /// Serializes the message including the header for network with given magic.
+    pub fn serialize<W: Write>(&self, writer: &mut W, magic: u32) -> io::Result<()> {
+        let mut payload = Vec::new();
+        self.serialize_payload(&mut payload)?;
+
+        write_message(writer, magic, self.command_name(), &payload)
+    }
+}
+
+/// Maximum number of headers in `headers` message accepted by Bitcoin Core.
+const MAX_HEADERS: u64 = 2000;
+
+/// Payload of `headers` message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Headers(pub Vec<BlockHeader>);
+
+impl Headers {
+    /// Deserializes the headers from network data.
+    ///
+    /// Each header is followed by transaction count, which must be zero.
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let count = deserialize_varint(reader)?;
+        if count > MAX_HEADERS {
+            return Err(ParseError::TooManyHeaders { count });
+        }
+
+        let mut headers = Vec::with_capacity(limits::preallocation(count));
+        for _ in 0..count {
+            headers.push(BlockHeader::deserialize(reader)?);
+            let tx_count = deserialize_varint(reader)?;
+            if tx_count != 0 {
+                return Err(ParseError::UnexpectedTransactionCount { count: tx_count });
+            }
+        }
+
+        Ok(Headers(headers))
+    }
+
+    /// Serializes the headers the same way they're sent over network
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        serialize_varint(writer, self.0.len() as u64)?;
+        for header in &self.0 {
+            header.serialize(writer)?;
+            // transaction count
+            writer.write_u8(0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Maximum number of hashes in block locator accepted by Bitcoin Core.
+const MAX_LOCATOR_HASHES: u64 = 101;
+
+/// Payload of `getblocks` and `getheaders` messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockLocator {
+    /// Protocol version of the node
+    pub version: u32,
+    /// Hashes of known blocks, starting with the best one and going back with increasing steps
+    pub hashes: Vec<Hash256>,
+    /// Hash of the last requested block, all zeros to request as many as possible
+    pub stop_hash: Hash256,
+}
+
+impl BlockLocator {
+    /// Deserializes the locator from network data
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let version = reader.read_u32::<LE>()?;
+        let count = deserialize_varint(reader)?;
+        if count > MAX_LOCATOR_HASHES {
+            return Err(ParseError::TooManyLocatorHashes { count });
+        }
+
+        let mut hashes = Vec::with_capacity(limits::preallocation(count));
+        for _ in 0..count {
+            hashes.push(Hash256::deserialize(reader)?);
+        }
+        let stop_hash = Hash256::deserialize(reader)?;
+
+        Ok(BlockLocator {
+            version,
+            hashes,
+            stop_hash,
+        })
+    }
+
+    /// Serializes the locator the same way it's sent over network
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_u32::<LE>(self.version)?;
+        serialize_varint(writer, self.hashes.len() as u64)?;
+        for hash in &self.hashes {
+            hash.serialize(writer)?;
+        }
+        self.stop_hash.serialize(writer)
+    }
+}
+
+/// Maximum number of items in inventory message accepted by Bitcoin Core.
+const MAX_INVENTORY_ITEMS: u64 = 50_000;
+
+/// Type of object announced or requested in inventory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InvType {
+    /// `MSG_TX`
+    Tx,
+    /// `MSG_BLOCK`
+    Block,
+    /// `MSG_FILTERED_BLOCK` (BIP37)
+    FilteredBlock,
+    /// `MSG_CMPCT_BLOCK` (BIP152)
+    CompactBlock,
+    /// `MSG_WITNESS_TX` (BIP144)
+    WitnessTx,
+    /// `MSG_WITNESS_BLOCK` (BIP144)
+    WitnessBlock,
+    /// `MSG_FILTERED_WITNESS_BLOCK`
+    FilteredWitnessBlock,
+    /// Type not known to this library
+    Unknown(u32),
+}
+
+impl InvType {
+    /// Flag marking requests for objects including witness. (BIP144)
+    const WITNESS_FLAG: u32 = 1 << 30;
+
+    /// Interprets the raw type.
+    pub fn from_raw(value: u32) -> Self {
+        match value {
+            1 => InvType::Tx,
+            2 => InvType::Block,
+            3 => InvType::FilteredBlock,
+            4 => InvType::CompactBlock,
+            x if x == InvType::WITNESS_FLAG | 1 => InvType::WitnessTx,
+            x if x == InvType::WITNESS_FLAG | 2 => InvType::WitnessBlock,
+            x if x == InvType::WITNESS_FLAG | 3 => InvType::FilteredWitnessBlock,
+            x => InvType::Unknown(x),
+        }
+    }
+
+    /// Returns the raw value of the type as sent over network.
+    pub fn to_raw(self) -> u32 {
+        match self {
+            InvType::Tx => 1,
+            InvType::Block => 2,
+            InvType::FilteredBlock => 3,
+            InvType::CompactBlock => 4,
+            InvType::WitnessTx => InvType::WITNESS_FLAG | 1,
+            InvType::WitnessBlock => InvType::WITNESS_FLAG | 2,
+            InvType::FilteredWitnessBlock => InvType::WITNESS_FLAG | 3,
+            InvType::Unknown(x) => x,
+        }
+    }
+}
+
+/// Single entry of inventory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvItem {
+    pub inv_type: InvType,
+    /// Hash of the object - txid or block hash
+    pub hash: Hash256,
+}
+
+impl InvItem {
+    /// Deserializes the item from network data
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let inv_type = InvType::from_raw(reader.read_u32::<LE>()?);
+        let hash = Hash256::deserialize(reader)?;
+
+        Ok(InvItem {
+            inv_type,
+            hash,
+        })
+    }
+
+    /// Serializes the item the same way it's sent over network
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_u32::<LE>(self.inv_type.to_raw())?;
+        self.hash.serialize(writer)
+    }
+}
+
+/// Payload of `inv`, `getdata` and `notfound` messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inventory(pub Vec<InvItem>);
+
+impl Inventory {
+    /// Deserializes the inventory from network data
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let count = deserialize_varint(reader)?;
+        if count > MAX_INVENTORY_ITEMS {
+            return Err(ParseError::TooManyInventoryItems { count });
+        }
+
+        let mut items = Vec::with_capacity(limits::preallocation(count));
+        for _ in 0..count {
+            items.push(InvItem::deserialize(reader)?);
+        }
+
+        Ok(Inventory(items))
+    }
+
+    /// Serializes the inventory the same way it's sent over network
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        serialize_varint(writer, self.0.len() as u64)?;
+        for item in &self.0 {
+            item.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// String prefixed with varint length, as used in P2P messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarStr(pub String);
+
+impl VarStr {
+    /// Deserializes the string, which must be valid UTF-8.
+    ///
+    /// The length is limited to the maximum message payload length.
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let len = deserialize_varint(reader)?;
+        if len > u64::from(MAX_PAYLOAD_LEN) {
+            return Err(ParseError::StringTooLong { len });
+        }
+
+        let mut data = Vec::with_capacity(limits::preallocation(len));
+        reader.take(len).read_to_end(&mut data)?;
+        if data.len() as u64 != len {
+            return Err(ParseError::UnexpectedEof);
+        }
+        String::from_utf8(data).map(VarStr).map_err(|_| ParseError::InvalidUtf8)
+    }
+
+    /// Serializes the string including its length.
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        serialize_varint(writer, self.0.len() as u64)?;
+        writer.write_all(self.0.as_bytes())
+    }
+}
+
+impl fmt::Display for VarStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Address of a node, as used in `version` and `addr` messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetAddr {
+    /// Bit field of services provided by the node
+    pub services: u64,
+    /// IPv6 address, IPv4 addresses are mapped as `::ffff:a.b.c.d`
+    pub ip: [u8; 16],
+    /// Big endian on the wire, unlike the rest of the protocol
+    pub port: u16,
+}
+
+impl NetAddr {
+    /// Deserializes the address from network data
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let services = reader.read_u64::<LE>()?;
+        let ip = read_array(reader)?;
+        let port = reader.read_u16::<BE>()?;
+
+        Ok(NetAddr {
+            services,
+            ip,
+            port,
+        })
+    }
+
+    /// Serializes the address the same way it's sent over network
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_u64::<LE>(self.services)?;
+        writer.write_all(&self.ip)?;
+        writer.write_u16::<BE>(self.port)
+    }
+
+    /// Returns the IP address, converting IPv4-mapped address to IPv4.
+    pub fn ip_addr(&self) -> IpAddr {
+        let ip = Ipv6Addr::from(self.ip);
+        match ip.to_ipv4_mapped() {
+            Some(ipv4) => IpAddr::V4(ipv4),
+            None => IpAddr::V6(ip),
+        }
+    }
+}
+
+/// Address of a node together with the time it was last seen, as used in `addr` message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampedNetAddr {
+    /// UNIX timestamp in seconds
+    pub timestamp: u32,
+    pub address: NetAddr,
+}
+
+impl TimestampedNetAddr {
+    /// Deserializes the address from network data
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let timestamp = reader.read_u32::<LE>()?;
+        let address = NetAddr::deserialize(reader)?;
+
+        Ok(TimestampedNetAddr {
+            timestamp,
+            address,
+        })
+    }
+
+    /// Serializes the address the same way it's sent over network
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_u32::<LE>(self.timestamp)?;
+        self.address.serialize(writer)
+    }
+}
+
+/// Maximum number of addresses in `addr` message accepted by Bitcoin Core.
+const MAX_ADDRESSES: u64 = 1000;
+
+/// Payload of `addr` message announcing addresses of other nodes.
+///
+/// Only the original format is supported, `addrv2` (BIP155) isn't.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddrMessage(pub Vec<TimestampedNetAddr>);
+
+impl AddrMessage {
+    /// Deserializes the addresses from network data
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let count = deserialize_varint(reader)?;
+        if count > MAX_ADDRESSES {
+            return Err(ParseError::TooManyAddresses { count });
+        }
+
+        let mut addresses = Vec::with_capacity(limits::preallocation(count));
+        for _ in 0..count {
+            addresses.push(TimestampedNetAddr::deserialize(reader)?);
+        }
+
+        Ok(AddrMessage(addresses))
+    }
+
+    /// Serializes the addresses the same way they're sent over network
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        serialize_varint(writer, self.0.len() as u64)?;
+        for address in &self.0 {
+            address.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Maximum length of user agent accepted by Bitcoin Core.
+const MAX_USER_AGENT_LEN: usize = 256;
+
+/// Payload of `version` message sent when the connection is established.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionMessage {
+    /// Protocol version of the node
+    pub version: i32,
+    /// Bit field of services provided by the node
+    pub services: u64,
+    /// UNIX timestamp in seconds
+    pub timestamp: i64,
+    /// Address of the node receiving the message
+    pub receiver: NetAddr,
+    /// Address of the node sending the message, often zeroed
+    pub sender: NetAddr,
+    /// Random value used to detect connections to self
+    pub nonce: u64,
+    /// Name and version of the software, e.g. `/Satoshi:25.0.0/` (BIP14)
+    pub user_agent: VarStr,
+    /// Height of the best block of the sending node
+    pub start_height: i32,
+    /// Whether the node wants transactions announced before filter is set (BIP37)
+    pub relay: bool,
+}
+
+impl VersionMessage {
+    /// Deserializes the payload of `version` message.
+    ///
+    /// The relay flag is missing in messages of old nodes, it's `true` then.
+    /// Its absence is detected by hitting the end of the reader, so the reader
+    /// must be limited to the payload, otherwise the first byte of the next
+    /// message would be read as the flag.
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let version = reader.read_i32::<LE>()?;
+        let services = reader.read_u64::<LE>()?;
+        let timestamp = reader.read_i64::<LE>()?;
+        let receiver = NetAddr::deserialize(reader)?;
+        let sender = NetAddr::deserialize(reader)?;
+        let nonce = reader.read_u64::<LE>()?;
+        let user_agent = VarStr::deserialize(reader)?;
+        if user_agent.0.len() > MAX_USER_AGENT_LEN {
+            return Err(ParseError::StringTooLong { len: user_agent.0.len() as u64 });
+        }
+        let start_height = reader.read_i32::<LE>()?;
+
+        let mut relay = [1];
+        loop {
+            match reader.read(&mut relay) {
+                Ok(_) => break,
+                Err(ref error) if error.kind() == io::ErrorKind::Interrupted => (),
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        Ok(VersionMessage {
+            version,
+            services,
+            timestamp,
+            receiver,
+            sender,
+            nonce,
+            user_agent,
+            start_height,
+            relay: relay[0] != 0,
+        })
+    }
+
+    /// Serializes the payload, always including the relay flag
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_i32::<LE>(self.version)?;
+        writer.write_u64::<LE>(self.services)?;
+        writer.write_i64::<LE>(self.timestamp)?;
+        self.receiver.serialize(writer)?;
+        self.sender.serialize(writer)?;
+        writer.write_u64::<LE>(self.nonce)?;
+        self.user_agent.serialize(writer)?;
+        writer.write_i32::<LE>(self.start_height)?;
+        writer.write_u8(self.relay as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex;
+    use crate::test_vectors::*;
+
+    /// Magic of mainnet messages.
+    const MAGIC: u32 = 0xd9b4_bef9;
+
+    fn tx_message() -> Vec<u8> {
+        let message = NetworkMessage {
+            magic: MAGIC,
+            transaction: Transaction::from_hex(TX_170).unwrap(),
+        };
+        let mut data = Vec::new();
+        message.serialize(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn tx_round_trip() {
+        let data = tx_message();
+        assert_eq!(&data[4..16], b"tx\0\0\0\0\0\0\0\0\0\0");
+
+        let message = NetworkMessage::deserialize(&mut &*data).unwrap();
+        assert_eq!(message.magic, MAGIC);
+        assert_eq!(message.transaction.txid().to_string(), TX_170_TXID);
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let mut data = tx_message();
+        data[20] ^= 1;
+        assert!(matches!(NetworkMessage::deserialize(&mut &*data), Err(ParseError::ChecksumMismatch)));
+    }
+
+    #[test]
+    fn unexpected_command() {
+        let mut data = tx_message();
+        data[4..6].copy_from_slice(b"xx");
+        match NetworkMessage::deserialize(&mut &*data) {
+            Err(ParseError::UnexpectedCommand { command }) => assert_eq!(&command[..2], b"xx"),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn payload_longer_than_data() {
+        let header = MessageHeader {
+            magic: MAGIC,
+            command: *b"tx\0\0\0\0\0\0\0\0\0\0",
+            length: MAX_PAYLOAD_LEN,
+            checksum: [0; 4],
+        };
+        assert!(matches!(header.read_payload(&mut &[0u8; 10][..]), Err(ParseError::UnexpectedEof)));
+
+        let header = MessageHeader {
+            length: MAX_PAYLOAD_LEN + 1,
+            ..header
+        };
+        assert!(matches!(header.read_payload(&mut &[0u8; 10][..]), Err(ParseError::PayloadTooLong { .. })));
+    }
+
+    #[test]
+    fn inventory_with_unknown_type() {
+        let mut data = vec![3];
+        for &(inv_type, byte) in &[(1u32, 0x11), (0x4000_0002, 0x22), (7, 0x33)] {
+            data.extend_from_slice(&inv_type.to_le_bytes());
+            data.extend_from_slice(&[byte; 32]);
+        }
+
+        let inventory = Inventory::deserialize(&mut &*data).unwrap();
+        let types = inventory.0.iter().map(|item| item.inv_type).collect::<Vec<_>>();
+        assert_eq!(types, [InvType::Tx, InvType::WitnessBlock, InvType::Unknown(7)]);
+        assert_eq!(inventory.0[2].hash, Hash256([0x33; 32]));
+
+        let mut serialized = Vec::new();
+        inventory.serialize(&mut serialized).unwrap();
+        assert_eq!(serialized, data);
+    }
+
+    #[test]
+    fn version() {
+        // sent by Bitcoin Core 0.9.99 in 2014, from rust-bitcoin tests
+        let payload = hex::decode("721101000100000000000000e6e0845300000000010000000000000000000000000000000000ffff0000000000000100000000000000fd87d87eeb4364f22cf54dca59412db7208d47d920cffce83ee8102f5361746f7368693a302e392e39392f2c9f040001").unwrap();
+        let version = VersionMessage::deserialize(&mut &*payload).unwrap();
+        assert_eq!(version.version, 70002);
+        assert_eq!(version.services, 1);
+        assert_eq!(version.timestamp, 1401217254);
+        assert_eq!(version.receiver.services, 1);
+        assert_eq!(version.receiver.ip[10..], [0xff, 0xff, 0, 0, 0, 0]);
+        assert_eq!(version.receiver.port, 0);
+        assert_eq!(version.nonce, 16735069437859780935);
+        assert_eq!(version.user_agent.0, "/Satoshi:0.9.99/");
+        assert_eq!(version.start_height, 302892);
+        assert!(version.relay);
+
+        let mut serialized = Vec::new();
+        version.serialize(&mut serialized).unwrap();
+        assert_eq!(serialized, payload);
+
+        let mut no_relay = payload.clone();
+        *no_relay.last_mut().unwrap() = 0;
+        assert!(!VersionMessage::deserialize(&mut &*no_relay).unwrap().relay);
+
+        // old nodes don't send the flag
+        let old = &payload[..payload.len() - 1];
+        assert!(VersionMessage::deserialize(&mut &*old).unwrap().relay);
+    }
+
+    #[test]
+    fn headers() {
+        let genesis = &GENESIS_BLOCK[..160];
+        let other = &BLOCK_TWO_TXS[..160];
+        let payload = hex::decode(&format!("02{}00{}00ff", genesis, other)).unwrap();
+
+        let mut reader = &*payload;
+        let headers = Headers::deserialize(&mut reader).unwrap();
+        assert_eq!(reader, [0xff]);
+        assert_eq!(headers.0.len(), 2);
+        assert_eq!(headers.0[0].time, 1231006505);
+        assert_eq!(headers.0[1], BlockHeader::deserialize(&mut &*hex::decode(other).unwrap()).unwrap());
+
+        let mut serialized = Vec::new();
+        headers.serialize(&mut serialized).unwrap();
+        assert_eq!(serialized, payload[..payload.len() - 1]);
+
+        let with_transactions = hex::decode(&format!("01{}01", genesis)).unwrap();
+        match Headers::deserialize(&mut &*with_transactions) {
+            Err(ParseError::UnexpectedTransactionCount { count: 1 }) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn verack_is_empty() {
+        // checksum of empty payload is the beginning of its double SHA256
+        let data = hex::decode("f9beb4d976657261636b000000000000000000005df6e0e2").unwrap();
+        let mut reader = &*data;
+        assert_eq!(P2PMessage::deserialize(&mut reader).unwrap(), P2PMessage::Verack);
+        assert!(reader.is_empty());
+
+        let mut serialized = Vec::new();
+        P2PMessage::Verack.serialize(&mut serialized, MAGIC).unwrap();
+        assert_eq!(serialized, data);
+
+        let mut command = [0; 12];
+        command[..6].copy_from_slice(b"verack");
+        match P2PMessage::from_payload(&command, vec![0]) {
+            Err(ParseError::LengthMismatch { expected: 1, actual: 0 }) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn ping_nonce() {
+        let mut data = Vec::new();
+        write_message(&mut data, MAGIC, b"ping", &[0xef, 0xcd, 0xab, 0x89, 0x67, 0x45, 0x23, 0x01]).unwrap();
+        let message = P2PMessage::deserialize(&mut &*data).unwrap();
+        assert_eq!(message, P2PMessage::Ping(0x0123456789abcdef));
+        assert_eq!(message.command_name(), b"ping");
+
+        let mut serialized = Vec::new();
+        message.serialize(&mut serialized, MAGIC).unwrap();
+        assert_eq!(serialized, data);
+
+        let mut command = [0; 12];
+        command[..4].copy_from_slice(b"ping");
+        assert!(matches!(P2PMessage::from_payload(&command, vec![0; 7]), Err(ParseError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn block_locator() {
+        let best = Hash256([0x11; 32]);
+        let older = Hash256([0x22; 32]);
+        let mut payload = vec![0x80, 0x11, 0x01, 0x00, 0x02];
+        payload.extend_from_slice(&best.0);
+        payload.extend_from_slice(&older.0);
+        payload.extend_from_slice(&[0; 32]);
+
+        let mut reader = &*payload;
+        let locator = BlockLocator::deserialize(&mut reader).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(locator, BlockLocator { version: 70016, hashes: vec![best, older], stop_hash: Hash256([0; 32]) });
+
+        let mut serialized = Vec::new();
+        locator.serialize(&mut serialized).unwrap();
+        assert_eq!(serialized, payload);
+
+        assert!(matches!(BlockLocator::deserialize(&mut &payload[..payload.len() - 1]), Err(ParseError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn net_addr() {
+        // 10.0.0.1:8333 with NODE_NETWORK, taken from rust-bitcoin
+        let data = hex::decode("010000000000000000000000000000000000ffff0a000001208d").unwrap();
+        let address = NetAddr::deserialize(&mut &*data).unwrap();
+        assert_eq!(address.services, 1);
+        assert_eq!(address.port, 8333);
+        assert_eq!(address.ip_addr(), IpAddr::V4([10, 0, 0, 1].into()));
+
+        let mut serialized = Vec::new();
+        address.serialize(&mut serialized).unwrap();
+        assert_eq!(serialized, data);
+
+        let ipv6 = NetAddr {
+            services: 0,
+            ip: [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+            port: 18333,
+        };
+        assert_eq!(ipv6.ip_addr(), IpAddr::V6("2001:db8::1".parse().unwrap()));
+
+        assert!(NetAddr::deserialize(&mut &data[..24]).unwrap_err().is_unexpected_eof());
+    }
+}
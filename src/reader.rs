@@ -0,0 +1,474 @@
+use std::cmp;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::{BufRead, Read};
+use byteorder::{LE, ByteOrder, ReadBytesExt};
+use crate::error::ParseError;
+use crate::limits;
+use crate::limits::Limits;
+use crate::transaction::Transaction;
+use crate::block::Block;
+use crate::address::Network;
+
+/// Reads exactly `N` bytes into an array.
+///
+/// The length is checked at compile time, so there's no slice to index wrongly.
+pub fn read_array<const N: usize, R: Read + ?Sized>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut buf = [0; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// Reader which counts the bytes read through it.
+pub(crate) struct CountingReader<R: Read> {
+    inner: R,
+    count: usize,
+}
+
+impl<R: Read> CountingReader<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        CountingReader {
+            inner,
+            count: 0,
+        }
+    }
+
+    /// Returns the number of bytes read so far.
+    pub(crate) fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.count += len;
+        Ok(len)
+    }
+}
+
+/// Error returned by `LimitedReader` inside `io::Error`.
+#[derive(Debug)]
+pub(crate) struct SizeLimitExceeded {
+    pub(crate) limit: u64,
+}
+
+impl fmt::Display for SizeLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "read more than {} bytes", self.limit)
+    }
+}
+
+impl Error for SizeLimitExceeded {}
+
+/// Reader which fails when more than given number of bytes is requested from it.
+///
+/// Protects against malformed data making the parser read endlessly. The
+/// error converts to `ParseError::SizeLimitExceeded`.
+pub struct LimitedReader<R: Read> {
+    inner: R,
+    limit: u64,
+    count: u64,
+}
+
+impl<R: Read> LimitedReader<R> {
+    /// Default limit, same as in `Limits::consensus`.
+    pub const DEFAULT_LIMIT: u64 = 4_000_000;
+
+    /// Creates the reader failing after `limit` bytes.
+    pub fn new(inner: R, limit: u64) -> Self {
+        LimitedReader {
+            inner,
+            limit,
+            count: 0,
+        }
+    }
+
+    /// Creates the reader with `DEFAULT_LIMIT`.
+    pub fn with_default_limit(inner: R) -> Self {
+        LimitedReader::new(inner, Self::DEFAULT_LIMIT)
+    }
+
+    /// Returns the number of bytes read so far.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let remaining = self.limit - self.count;
+        if remaining == 0 {
+            return Err(io::Error::other(SizeLimitExceeded { limit: self.limit }));
+        }
+
+        let max_len = cmp::min(buf.len() as u64, remaining) as usize;
+        let len = self.inner.read(&mut buf[..max_len])?;
+        self.count += len as u64;
+        Ok(len)
+    }
+}
+
+/// Reads transactions stored back to back in a single stream.
+///
+/// The iterator ends when the stream ends between two transactions. If the
+/// stream ends in the middle of a transaction, an error for which
+/// `ParseError::is_unexpected_eof` returns `true` is returned instead. The iterator ends after returning any error, since
+/// the position in the stream is unknown.
+///
+/// The stream is buffered internally, which is needed to check for its end
+/// without consuming any data.
+pub struct TransactionReader<R: Read> {
+    reader: io::BufReader<R>,
+    failed: bool,
+}
+
+impl<R: Read> TransactionReader<R> {
+    /// Creates the reader of transactions.
+    pub fn new(reader: R) -> Self {
+        TransactionReader::from_buf_reader(io::BufReader::new(reader))
+    }
+
+    /// Creates the reader of transactions with buffer of given capacity.
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
+        TransactionReader::from_buf_reader(io::BufReader::with_capacity(capacity, reader))
+    }
+
+    fn from_buf_reader(reader: io::BufReader<R>) -> Self {
+        TransactionReader {
+            reader,
+            failed: false,
+        }
+    }
+
+    /// Returns the underlying reader.
+    ///
+    /// Data buffered but not parsed yet is lost.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+}
+
+impl<R: Read> Iterator for TransactionReader<R> {
+    type Item = Result<Transaction, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        // Empty buffer means the stream ended cleanly before the next transaction.
+        loop {
+            match self.reader.fill_buf() {
+                Ok([]) => return None,
+                Ok(_) => break,
+                Err(ref error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => {
+                    self.failed = true;
+                    return Some(Err(error.into()));
+                },
+            }
+        }
+
+        let result = Transaction::deserialize(&mut self.reader);
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
+
+/// Decodes transactions from a buffer which may not contain all the data yet.
+///
+/// Intended for network code collecting data from a socket: after receiving
+/// more bytes, call `decode` with the whole buffer again. Nothing is consumed
+/// until a complete transaction is available.
+#[derive(Debug, Clone, Default)]
+pub struct TransactionDecoder {
+    limits: Limits,
+}
+
+impl TransactionDecoder {
+    /// Creates the decoder checking consensus limits.
+    pub fn new() -> Self {
+        TransactionDecoder::default()
+    }
+
+    /// Creates the decoder checking given limits.
+    pub fn with_limits(limits: Limits) -> Self {
+        TransactionDecoder {
+            limits,
+        }
+    }
+
+    /// Decodes the transaction from the beginning of the buffer.
+    ///
+    /// Returns `Ok(None)` if the buffer ends before the transaction does, in
+    /// which case more data is needed. Otherwise returns the transaction and
+    /// the number of bytes it occupies, which the caller should remove from the buffer.
+    pub fn decode(&mut self, buf: &[u8]) -> Result<Option<(Transaction, usize)>, ParseError> {
+        let mut reader = CountingReader::new(buf);
+        match Transaction::deserialize_with_limits(&mut reader, &self.limits) {
+            Ok(transaction) => Ok(Some((transaction, reader.count()))),
+            Err(ref error) if error.is_unexpected_eof() => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Reads blocks from the `blkNNNNN.dat` files of Bitcoin Core.
+///
+/// Each block is prefixed with network magic and its length as 32-bit little
+/// endian integers. Core preallocates the files, so the iterator ends at zero
+/// magic as well as at the end of the stream. Like `TransactionReader`, it ends
+/// after returning any error.
+pub struct BlockFileReader<R: Read> {
+    reader: R,
+    network: Network,
+    finished: bool,
+}
+
+impl<R: Read> BlockFileReader<R> {
+    /// Maximum length of a block, same as the maximum weight of it. (BIP141)
+    const MAX_BLOCK_LEN: u32 = 4_000_000;
+
+    /// Creates the reader of blocks, checking the magic of given network.
+    pub fn new(reader: R, network: Network) -> Self {
+        BlockFileReader {
+            reader,
+            network,
+            finished: false,
+        }
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the magic, returning `None` if the stream ended right before it.
+    fn read_magic(&mut self) -> Result<Option<u32>, ParseError> {
+        let mut magic = [0; 4];
+        let mut filled = 0;
+        while filled < magic.len() {
+            match self.reader.read(&mut magic[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(ParseError::UnexpectedEof),
+                Ok(len) => filled += len,
+                Err(ref error) if error.kind() == io::ErrorKind::Interrupted => (),
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        Ok(Some(LE::read_u32(&magic)))
+    }
+
+    /// Reads the block following the magic.
+    fn read_block(&mut self) -> Result<Block, ParseError> {
+        let len = self.reader.read_u32::<LE>()?;
+        if len > Self::MAX_BLOCK_LEN {
+            return Err(ParseError::BlockTooLarge { len });
+        }
+
+        let mut data = Vec::with_capacity(limits::preallocation(len.into()));
+        self.reader.by_ref().take(len.into()).read_to_end(&mut data)?;
+        if data.len() < len as usize {
+            return Err(ParseError::UnexpectedEof);
+        }
+
+        let mut block_reader = &*data;
+        let block = Block::deserialize(&mut block_reader)?;
+        if !block_reader.is_empty() {
+            return Err(ParseError::LengthMismatch {
+                expected: data.len(),
+                actual: data.len() - block_reader.len(),
+            });
+        }
+
+        Ok(block)
+    }
+}
+
+impl<R: Read> Iterator for BlockFileReader<R> {
+    type Item = Result<Block, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let result = match self.read_magic() {
+            Ok(None) | Ok(Some(0)) => {
+                self.finished = true;
+                return None;
+            },
+            Ok(Some(magic)) if magic != self.network.magic() => Err(ParseError::InvalidMagic { magic }),
+            Ok(Some(_)) => self.read_block(),
+            Err(error) => Err(error),
+        };
+        self.finished = result.is_err();
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex;
+    use crate::test_vectors::*;
+
+    #[test]
+    fn limited_reader() {
+        let data = hex::decode(TX_170).unwrap();
+        assert!(data.len() > 100);
+
+        let mut reader = LimitedReader::new(&*data, 100);
+        let error = Transaction::deserialize(&mut reader).unwrap_err();
+        assert!(matches!(error.root_cause(), ParseError::SizeLimitExceeded { limit: 100 }), "{:?}", error);
+        assert_eq!(reader.count(), 100);
+
+        let mut reader = LimitedReader::new(&*data, data.len() as u64);
+        assert!(Transaction::deserialize(&mut reader).is_ok());
+        assert_eq!(reader.count(), data.len() as u64);
+    }
+
+    #[test]
+    fn three_transactions() {
+        let mut data = hex::decode(TX_170).unwrap();
+        data.extend(hex::decode(BIP143_P2WPKH).unwrap());
+        data.extend(hex::decode(TX_170).unwrap());
+
+        let mut reader = TransactionReader::new(&*data);
+        for txid in &[TX_170_TXID, BIP143_P2WPKH_TXID, TX_170_TXID] {
+            assert_eq!(reader.next().unwrap().unwrap().txid().to_string(), *txid);
+        }
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn truncated_transaction() {
+        let mut data = hex::decode(TX_170).unwrap();
+        data.extend(hex::decode(TX_170).unwrap());
+        data.pop();
+
+        let mut reader = TransactionReader::new(&*data);
+        assert!(reader.next().unwrap().is_ok());
+        let error = reader.next().unwrap().unwrap_err();
+        assert!(matches!(error.root_cause(), ParseError::UnexpectedEof));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn tiny_buffer() {
+        let mut data = hex::decode(TX_170).unwrap();
+        data.extend(hex::decode(BIP143_P2WPKH).unwrap());
+
+        // Each `fill_buf` returns a single byte, so the transaction must be parsed across refills.
+        let mut reader = TransactionReader::with_capacity(1, &*data);
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().is_none());
+
+        data.truncate(data.len() - 1);
+        let mut reader = TransactionReader::with_capacity(1, &*data);
+        assert!(reader.next().unwrap().is_ok());
+        assert!(matches!(reader.next().unwrap().unwrap_err().root_cause(), ParseError::UnexpectedEof));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn decoder_needs_more() {
+        let data = hex::decode(BIP143_P2WPKH).unwrap();
+        let mut decoder = TransactionDecoder::new();
+        let mut buf = Vec::new();
+
+        // arrives in chunks, as from a socket
+        for chunk in data.chunks(50) {
+            assert!(decoder.decode(&buf).unwrap().is_none(), "decoded from {} bytes", buf.len());
+            buf.extend_from_slice(chunk);
+        }
+        let (transaction, len) = decoder.decode(&buf).unwrap().unwrap();
+        assert_eq!(len, data.len());
+        assert_eq!(transaction.txid().to_string(), BIP143_P2WPKH_TXID);
+    }
+
+    #[test]
+    fn decoder_trailing_bytes() {
+        let mut data = hex::decode(TX_170).unwrap();
+        let len = data.len();
+        data.extend(hex::decode(BIP143_P2WPKH).unwrap());
+
+        let mut decoder = TransactionDecoder::new();
+        let (transaction, consumed) = decoder.decode(&data).unwrap().unwrap();
+        assert_eq!(consumed, len);
+        assert_eq!(transaction.txid().to_string(), TX_170_TXID);
+        let (transaction, consumed) = decoder.decode(&data[len..]).unwrap().unwrap();
+        assert_eq!(consumed, data.len() - len);
+        assert_eq!(transaction.txid().to_string(), BIP143_P2WPKH_TXID);
+    }
+
+    #[test]
+    fn decoder_malformed() {
+        // SegWit marker followed by invalid flag
+        let mut data = hex::decode(BIP143_P2WPKH).unwrap();
+        data[5] = 0x00;
+
+        let error = TransactionDecoder::new().decode(&data).unwrap_err();
+        assert!(matches!(error, ParseError::InvalidSegwitFlag { flag: 0 }), "{:?}", error);
+    }
+
+    fn framed_block(magic: u32, block: &str) -> Vec<u8> {
+        let block = hex::decode(block).unwrap();
+        let mut data = Vec::new();
+        data.extend_from_slice(&magic.to_le_bytes());
+        data.extend_from_slice(&(block.len() as u32).to_le_bytes());
+        data.extend(block);
+        data
+    }
+
+    #[test]
+    fn block_file() {
+        let mut data = framed_block(Network::Mainnet.magic(), GENESIS_BLOCK);
+        assert_eq!(data[..4], [0xf9, 0xbe, 0xb4, 0xd9]);
+        data.extend(framed_block(Network::Mainnet.magic(), BLOCK_TWO_TXS));
+        // preallocated space at the end of the file
+        data.extend_from_slice(&[0; 16]);
+
+        let mut reader = BlockFileReader::new(&*data, Network::Mainnet);
+        let genesis = reader.next().unwrap().unwrap();
+        assert_eq!(genesis, Block::deserialize(&mut &*hex::decode(GENESIS_BLOCK).unwrap()).unwrap());
+        let second = reader.next().unwrap().unwrap();
+        assert_eq!(second.transactions.len(), 2);
+        assert!(reader.next().is_none());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn block_file_errors() {
+        let data = framed_block(Network::Testnet.magic(), GENESIS_BLOCK);
+        let mut reader = BlockFileReader::new(&*data, Network::Mainnet);
+        match reader.next() {
+            Some(Err(ParseError::InvalidMagic { magic: 0x0709_110b })) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert!(reader.next().is_none());
+
+        let mut data = framed_block(Network::Mainnet.magic(), GENESIS_BLOCK);
+        data.truncate(data.len() - 1);
+        let mut reader = BlockFileReader::new(&*data, Network::Mainnet);
+        assert!(reader.next().unwrap().unwrap_err().is_unexpected_eof());
+        assert!(reader.next().is_none());
+
+        // ends in the middle of magic
+        let mut reader = BlockFileReader::new(&[0xf9, 0xbe][..], Network::Mainnet);
+        assert!(reader.next().unwrap().unwrap_err().is_unexpected_eof());
+    }
+}
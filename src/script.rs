@@ -0,0 +1,829 @@
+use byteorder::{ByteOrder, LE};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::{Read, Write};
+#[cfg(feature = "std")]
+use crate::error::ParseError;
+use crate::prelude::*;
+use crate::hex;
+use crate::opcodes::Opcode;
+use crate::hash::{Hash256, Hash160};
+#[cfg(feature = "std")]
+use crate::limits::{self, Limits};
+#[cfg(feature = "std")]
+use crate::varint::{deserialize_varint, serialize_varint};
+#[cfg(feature = "std")]
+use crate::reader::CountingReader;
+#[cfg(feature = "tokio")]
+use crate::varint::deserialize_varint_async;
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncReadExt};
+use crate::varint::varint_len;
+
+/// Maximum length of a script which can be executed. (consensus)
+const MAX_SCRIPT_LEN: usize = 10_000;
+
+/// Maximum length of standard `OP_RETURN` output, including the opcode and the push.
+const MAX_OP_RETURN_LEN: usize = 83;
+
+/// Represent's Bitcoin script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Script(pub Vec<u8>);
+
+#[cfg(feature = "std")]
+impl Script {
+    /// Deserializes the script from a reader.
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        Script::deserialize_with_limits(reader, &Limits::consensus())
+    }
+
+    /// Deserializes the script from a reader, checking length against given limits.
+    pub fn deserialize_with_limits<R: Read>(reader: &mut R, limits: &Limits) -> Result<Self, ParseError> {
+        let len = deserialize_varint(reader)?;
+        // This is consensus rule, so theoretically no need to check it,
+        // but serves as a protection against corrupted inputs.
+        if len > limits.max_script_len {
+            return Err(ParseError::ScriptTooLong { len });
+        }
+        limits::checked_len(len)?;
+
+        let mut reader = reader.by_ref().take(len);
+        let mut data = Vec::with_capacity(limits::preallocation(len));
+
+        io::copy(&mut reader, &mut data)?;
+        if data.len() as u64 != len {
+            return Err(ParseError::UnexpectedEof);
+        }
+        Ok(Script(data))
+    }
+
+    /// Deserializes the script, returning also the number of bytes consumed.
+    ///
+    /// The count includes the length prefix as it was encoded, so it can be
+    /// compared with the expected offsets when the parsing gets out of sync.
+    pub fn deserialize_counted<R: Read>(reader: &mut R) -> Result<(Self, usize), ParseError> {
+        Script::deserialize_counted_with_limits(reader, &Limits::consensus())
+    }
+
+    /// Deserializes the script checking given limits, returning also the number of bytes consumed.
+    pub fn deserialize_counted_with_limits<R: Read>(reader: &mut R, limits: &Limits) -> Result<(Self, usize), ParseError> {
+        let mut reader = CountingReader::new(reader);
+        let script = Script::deserialize_with_limits(&mut reader, limits)?;
+
+        Ok((script, reader.count()))
+    }
+
+    /// Advances the reader past the script without storing it, checking given limits.
+    pub fn skip<R: Read>(reader: &mut R, limits: &Limits) -> Result<(), ParseError> {
+        let len = deserialize_varint(reader)?;
+        if len > limits.max_script_len {
+            return Err(ParseError::ScriptTooLong { len });
+        }
+        limits::checked_len(len)?;
+
+        if io::copy(&mut reader.by_ref().take(len), &mut io::sink())? != len {
+            return Err(ParseError::UnexpectedEof);
+        }
+        Ok(())
+    }
+
+    /// Serializes the script including its length.
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        serialize_varint(writer, self.0.len() as u64)?;
+        writer.write_all(&self.0)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Script {
+    /// Deserializes the script from an async reader.
+    pub async fn deserialize_async<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self, ParseError> {
+        Script::deserialize_with_limits_async(reader, &Limits::consensus()).await
+    }
+
+    /// Deserializes the script from an async reader, checking length against given limits.
+    pub async fn deserialize_with_limits_async<R: AsyncRead + Unpin>(reader: &mut R, limits: &Limits) -> Result<Self, ParseError> {
+        let len = deserialize_varint_async(reader).await?;
+        if len > limits.max_script_len {
+            return Err(ParseError::ScriptTooLong { len });
+        }
+        limits::checked_len(len)?;
+
+        let mut data = Vec::with_capacity(limits::preallocation(len));
+        reader.take(len).read_to_end(&mut data).await?;
+        if data.len() as u64 != len {
+            return Err(ParseError::UnexpectedEof);
+        }
+        Ok(Script(data))
+    }
+}
+
+impl Script {
+    /// Encodes the script bytes as hex, without the length.
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.0)
+    }
+
+    /// Returns the number of bytes `serialize` writes, including the length.
+    pub fn serialized_len(&self) -> usize {
+        varint_len(self.0.len() as u64) + self.0.len()
+    }
+
+    /// Returns iterator over instructions of the script.
+    pub fn instructions(&self) -> Instructions<'_> {
+        Instructions {
+            data: &self.0,
+        }
+    }
+
+    /// Classifies the script by matching it against standard templates.
+    pub fn script_type(&self) -> ScriptType {
+        let len = self.0.len();
+        match self.0[..] {
+            // OP_DUP OP_HASH160 <20 bytes> OP_EQUALVERIFY OP_CHECKSIG
+            [0x76, 0xa9, 0x14, .., 0x88, 0xac] if len == 25 => ScriptType::P2pkh,
+            // OP_HASH160 <20 bytes> OP_EQUAL
+            [0xa9, 0x14, .., 0x87] if len == 23 => ScriptType::P2sh,
+            // OP_0 <20 bytes>
+            [0x00, 0x14, ..] if len == 22 => ScriptType::P2wpkh,
+            // OP_0 <32 bytes>
+            [0x00, 0x20, ..] if len == 34 => ScriptType::P2wsh,
+            // OP_RETURN ...
+            [0x6a, ..] => ScriptType::OpReturn,
+            _ if self.p2pk_pubkey().is_some() => ScriptType::P2pk,
+            _ => ScriptType::NonStandard,
+        }
+    }
+
+    /// Checks whether the output script can never be spent, same as `IsUnspendable` in Bitcoin Core.
+    ///
+    /// That is the case for scripts starting with `OP_RETURN` and scripts longer
+    /// than 10 000 bytes. Other scripts may be unspendable too, but it's not
+    /// easy to prove.
+    pub fn is_provably_unspendable(&self) -> bool {
+        self.0.first() == Some(&0x6a) || self.0.len() > MAX_SCRIPT_LEN
+    }
+
+    /// Checks whether the output script fits into the size limits of Bitcoin Core relay policy.
+    ///
+    /// Scripts longer than 10 000 bytes are unspendable by consensus. `OP_RETURN`
+    /// outputs are limited to 83 bytes, which allows 80 bytes of data. Other
+    /// standardness rules aren't checked.
+    pub fn is_standard_size(&self) -> bool {
+        match self.script_type() {
+            ScriptType::OpReturn => self.0.len() <= MAX_OP_RETURN_LEN,
+            _ => self.0.len() <= MAX_SCRIPT_LEN,
+        }
+    }
+
+    /// Returns the data pushed after `OP_RETURN` at the beginning of the script.
+    ///
+    /// If there are multiple pushes only the first one is returned. Returns
+    /// `None` if the script doesn't start with `OP_RETURN` followed by a push.
+    pub fn op_return_data(&self) -> Option<&[u8]> {
+        if self.0.first() != Some(&0x6a) {
+            return None;
+        }
+
+        let mut instructions = self.instructions();
+        instructions.next();
+        match instructions.next()? {
+            Ok(Instruction::PushBytes(data)) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Returns the public key if the script is pay to public key.
+    ///
+    /// The key must be either compressed (33 bytes starting with 0x02 or 0x03)
+    /// or uncompressed (65 bytes starting with 0x04). Whether it's a valid curve
+    /// point isn't checked.
+    pub fn p2pk_pubkey(&self) -> Option<&[u8]> {
+        match self.0[..] {
+            // <33 bytes> OP_CHECKSIG
+            [0x21, 0x02..=0x03, .., 0xac] if self.0.len() == 35 => Some(&self.0[1..34]),
+            // <65 bytes> OP_CHECKSIG
+            [0x41, 0x04, .., 0xac] if self.0.len() == 67 => Some(&self.0[1..66]),
+            _ => None,
+        }
+    }
+
+    /// Checks whether all pushes use the shortest possible encoding. (BIP62)
+    ///
+    /// Data 1 to 16 and 0x81 must be pushed using `OP_1` to `OP_16` and `OP_1NEGATE`,
+    /// other data using the shortest push opcode. Returns `false` if the script
+    /// can't be disassembled.
+    pub fn uses_minimal_pushes(&self) -> bool {
+        let mut instructions = self.instructions();
+        while let Some(&opcode) = instructions.data.first() {
+            match instructions.next() {
+                Some(Ok(Instruction::PushBytes(data))) if !is_minimal_push(opcode, data) => return false,
+                Some(Ok(_)) => (),
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Parses bare multisig script `OP_m <public key>... OP_n OP_CHECKMULTISIG`.
+    ///
+    /// This is the redeem script of multisig P2SH output. The `OP_0` preceding
+    /// signatures in the spending input is an extra item consumed because of
+    /// a bug in `OP_CHECKMULTISIG`, it's not part of this script. Returns `None`
+    /// if the number of keys doesn't match `n`, `m` is zero or greater than `n`
+    /// or any key isn't compressed or uncompressed public key.
+    pub fn parse_multisig(&self) -> Option<MultisigInfo<'_>> {
+        let mut instructions = self.instructions().collect::<Result<Vec<_>, _>>().ok()?;
+        if instructions.pop()? != Instruction::Op(Opcode::OP_CHECKMULTISIG) {
+            return None;
+        }
+        let total = small_int(instructions.pop()?)?;
+        if instructions.is_empty() {
+            return None;
+        }
+        let required = small_int(instructions.remove(0))?;
+
+        let public_keys = instructions
+            .into_iter()
+            .map(|instruction| match instruction {
+                Instruction::PushBytes(key) if is_public_key(key) => Some(key),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if public_keys.len() != total as usize || required == 0 || required > total {
+            return None;
+        }
+
+        Some(MultisigInfo {
+            required,
+            public_keys,
+        })
+    }
+
+    /// Returns the hash of the script as used in P2SH output, when the script is redeem script.
+    pub fn script_hash(&self) -> Hash160 {
+        Hash160::hash(&self.0)
+    }
+
+    /// Returns the hash of the script as used in P2WSH output, when the script is witness script.
+    ///
+    /// This is a single SHA256 with the bytes in the order it outputs them, which is
+    /// the order of the witness program. Note that `Display` shows them reversed, so
+    /// use `to_internal_bytes` to get the program.
+    pub fn witness_script_hash(&self) -> Hash256 {
+        Hash256::sha256(&self.0)
+    }
+
+    /// Returns witness version and program if the script is a witness program (BIP141).
+    pub fn witness_program(&self) -> Option<(u8, &[u8])> {
+        let version = match *self.0.first()? {
+            // OP_0
+            0x00 => 0,
+            // OP_1 ..= OP_16
+            opcode @ 0x51..=0x60 => opcode - 0x50,
+            _ => return None,
+        };
+        if self.0.len() < 4 || self.0.len() > 42 || self.0[1] as usize != self.0.len() - 2 {
+            return None;
+        }
+
+        Some((version, &self.0[2..]))
+    }
+}
+
+/// Checks whether `opcode` is the shortest one for pushing `data`.
+fn is_minimal_push(opcode: u8, data: &[u8]) -> bool {
+    match *data {
+        // OP_0
+        [] => opcode == 0x00,
+        // should be OP_1 to OP_16 or OP_1NEGATE
+        [1..=16] | [0x81] => false,
+        // OP_PUSHBYTES_1 to OP_PUSHBYTES_75
+        _ if data.len() <= 0x4b => opcode as usize == data.len(),
+        // OP_PUSHDATA1
+        _ if data.len() <= 0xFF => opcode == 0x4c,
+        // OP_PUSHDATA2
+        _ if data.len() <= 0xFFFF => opcode == 0x4d,
+        _ => true,
+    }
+}
+
+/// Returns the value of `OP_1` to `OP_16`.
+fn small_int(instruction: Instruction<'_>) -> Option<u8> {
+    match instruction {
+        Instruction::Op(opcode) => match u8::from(opcode) {
+            opcode @ 0x51..=0x60 => Some(opcode - 0x50),
+            _ => None,
+        },
+        Instruction::PushBytes(_) => None,
+    }
+}
+
+/// Checks whether the data looks like compressed or uncompressed public key.
+pub(crate) fn is_public_key(data: &[u8]) -> bool {
+    match *data {
+        [0x02..=0x03, ..] => data.len() == 33,
+        [0x04, ..] => data.len() == 65,
+        _ => false,
+    }
+}
+
+/// Multisig script parsed by `Script::parse_multisig`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultisigInfo<'a> {
+    /// Number of signatures required, `m`
+    pub required: u8,
+    /// Keys allowed to sign, `n` is their count
+    pub public_keys: Vec<&'a [u8]>,
+}
+
+impl<'a> MultisigInfo<'a> {
+    /// Returns the number of keys, `n`.
+    pub fn total(&self) -> usize {
+        self.public_keys.len()
+    }
+}
+
+/// Type of output script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScriptType {
+    /// Pay to public key
+    P2pk,
+    /// Pay to public key hash
+    P2pkh,
+    /// Pay to script hash (BIP16)
+    P2sh,
+    /// Pay to witness public key hash (BIP141)
+    P2wpkh,
+    /// Pay to witness script hash (BIP141)
+    P2wsh,
+    /// Unspendable output carrying data
+    OpReturn,
+    /// Script not matching any of the templates above
+    NonStandard,
+}
+
+/// Displays the script disassembled into opcodes, pushed data are in hex.
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, instruction) in self.instructions().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+
+            match instruction {
+                Ok(Instruction::PushBytes(&[])) => f.write_str("OP_0")?,
+                Ok(Instruction::PushBytes(data)) => hex::write(f, data)?,
+                Ok(Instruction::Op(opcode)) => f.write_str(opcode.name())?,
+                Err(_) => f.write_str("[error]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Single instruction of a script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction<'a> {
+    /// Pushes the data onto the stack, including `OP_0` pushing empty data.
+    PushBytes(&'a [u8]),
+    /// Any opcode that isn't a push of data.
+    Op(Opcode),
+}
+
+/// Error returned when script can't be disassembled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptError {
+    /// Push opcode claims more data than the script contains, or its length itself is cut off.
+    UnexpectedEnd,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ScriptError::UnexpectedEnd => write!(f, "push runs past the end of the script"),
+        }
+    }
+}
+
+impl Error for ScriptError {}
+
+/// Iterator over instructions of a script.
+///
+/// The iteration stops after the first error.
+pub struct Instructions<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Instructions<'a> {
+    /// Takes `len` bytes from the remaining data.
+    ///
+    /// The length is checked before slicing, so lengths claimed by malicious
+    /// scripts can't cause panics or huge allocations.
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ScriptError> {
+        if len > self.data.len() {
+            self.data = &[];
+            return Err(ScriptError::UnexpectedEnd);
+        }
+
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+}
+
+impl<'a> Iterator for Instructions<'a> {
+    type Item = Result<Instruction<'a>, ScriptError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&opcode, rest) = self.data.split_first()?;
+        self.data = rest;
+
+        let len = match opcode {
+            // OP_0 and OP_PUSHBYTES_1 to OP_PUSHBYTES_75
+            0x00..=0x4b => Ok(opcode as usize),
+            // OP_PUSHDATA1
+            0x4c => self.take(1).map(|len| len[0] as usize),
+            // OP_PUSHDATA2
+            0x4d => self.take(2).map(|len| LE::read_u16(len) as usize),
+            // OP_PUSHDATA4
+            0x4e => self.take(4).map(|len| LE::read_u32(len) as usize),
+            _ => return Some(Ok(Instruction::Op(opcode.into()))),
+        };
+
+        Some(len.and_then(|len| self.take(len)).map(Instruction::PushBytes))
+    }
+}
+
+/// Constructs scripts instruction by instruction.
+///
+/// This is the counterpart of `Instructions`.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptBuilder {
+    data: Vec<u8>,
+}
+
+impl ScriptBuilder {
+    /// Creates the builder of empty script.
+    pub fn new() -> Self {
+        ScriptBuilder::default()
+    }
+
+    /// Appends the opcode, given either as `Opcode` or raw byte.
+    pub fn push_opcode<O: Into<u8>>(mut self, opcode: O) -> Self {
+        self.data.push(opcode.into());
+        self
+    }
+
+    /// Appends instruction pushing the data, using the shortest push opcode.
+    ///
+    /// Data 1 to 16 and 0x81 are pushed using `OP_1` to `OP_16` and `OP_1NEGATE`,
+    /// so the script passes `Script::uses_minimal_pushes`.
+    ///
+    /// # Panics
+    ///
+    /// If the data is longer than `u32::MAX` bytes.
+    pub fn push_slice(mut self, data: &[u8]) -> Self {
+        match *data {
+            // OP_1 to OP_16
+            [value @ 1..=16] => return self.push_opcode(0x50 + value),
+            // OP_1NEGATE
+            [0x81] => return self.push_opcode(0x4f),
+            _ => (),
+        }
+
+        let len = data.len();
+        if len <= 0x4b {
+            // OP_0 and OP_PUSHBYTES_1 to OP_PUSHBYTES_75
+            self.data.push(len as u8);
+        } else if len <= 0xFF {
+            // OP_PUSHDATA1
+            self.data.push(0x4c);
+            self.data.push(len as u8);
+        } else if len <= 0xFFFF {
+            // OP_PUSHDATA2
+            self.data.push(0x4d);
+            self.data.extend_from_slice(&(len as u16).to_le_bytes());
+        } else {
+            // OP_PUSHDATA4
+            let len = u32::try_from(len).expect("data pushed to script too long");
+            self.data.push(0x4e);
+            self.data.extend_from_slice(&len.to_le_bytes());
+        }
+        self.data.extend_from_slice(data);
+        self
+    }
+
+    /// Returns the constructed script.
+    pub fn into_script(self) -> Script {
+        Script(self.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_p2pkh() {
+        let script = ScriptBuilder::new()
+            .push_opcode(Opcode::OP_DUP)
+            .push_opcode(Opcode::OP_HASH160)
+            .push_slice(&[0x62; 20])
+            .push_opcode(Opcode::OP_EQUALVERIFY)
+            .push_opcode(Opcode::OP_CHECKSIG)
+            .into_script();
+
+        assert_eq!(script.0.len(), 25);
+        assert_eq!(script.0[2], 20);
+        assert_eq!(script.script_type(), ScriptType::P2pkh);
+    }
+
+    #[test]
+    fn builder_push_lengths() {
+        let prefix = |len: usize| {
+            let script = ScriptBuilder::new().push_slice(&vec![0xaa; len]).into_script();
+            assert_eq!(script.0.len() - len, match len { 0..=0x4b => 1, 0x4c..=0xFF => 2, 0x100..=0xFFFF => 3, _ => 5 });
+            script.0[0]
+        };
+
+        assert_eq!(prefix(0), 0x00);
+        assert_eq!(prefix(0x4b), 0x4b);
+        assert_eq!(prefix(0x4c), 0x4c);
+        assert_eq!(prefix(0xFF), 0x4c);
+        assert_eq!(prefix(0x100), 0x4d);
+        assert_eq!(prefix(0x10000), 0x4e);
+    }
+
+    #[test]
+    fn builder_small_numbers() {
+        for value in 1..=16 {
+            assert_eq!(ScriptBuilder::new().push_slice(&[value]).into_script().0, [0x50 + value]);
+        }
+        assert_eq!(ScriptBuilder::new().push_slice(&[0x81]).into_script().0, [0x4f]);
+        assert_eq!(ScriptBuilder::new().push_slice(&[0x11]).into_script().0, [0x01, 0x11]);
+    }
+
+    #[test]
+    fn builder_pushes_are_minimal() {
+        let mut builder = ScriptBuilder::new();
+        for len in &[0, 1, 2, 0x4b, 0x4c, 0xFF, 0x100] {
+            builder = builder.push_slice(&vec![0x01; *len]);
+        }
+        for value in 0..=0xFF {
+            builder = builder.push_slice(&[value]);
+        }
+
+        assert!(builder.into_script().uses_minimal_pushes());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn op_return_data() {
+        use crate::test_vectors::SEGWIT_COINBASE;
+        use crate::transaction::Transaction;
+
+        let coinbase = Transaction::from_hex(SEGWIT_COINBASE).unwrap();
+        let commitment = &coinbase.outputs[1].verify_script;
+        let expected = hex::decode("aa21a9edf91c46b49eb8a29089980f02ee6b57e7d63d33b18b4fddac2bcd7db2a3983704").unwrap();
+        assert_eq!(commitment.op_return_data(), Some(&*expected));
+        assert_eq!(coinbase.outputs[0].verify_script.op_return_data(), None);
+
+        let pushdata1 = ScriptBuilder::new().push_opcode(0x6a).push_slice(&[0xaa; 80]).into_script();
+        assert_eq!(pushdata1.0[1], 0x4c);
+        assert_eq!(pushdata1.op_return_data(), Some(&[0xaa; 80][..]));
+
+        // only the first push is returned
+        let two_pushes = ScriptBuilder::new().push_opcode(0x6a).push_slice(b"ab").push_slice(b"cd").into_script();
+        assert_eq!(two_pushes.op_return_data(), Some(&b"ab"[..]));
+
+        assert_eq!(Script(vec![0x6a]).op_return_data(), None);
+        assert_eq!(Script(vec![0x6a, 0x4c]).op_return_data(), None);
+    }
+
+    #[test]
+    fn truncated_pushes() {
+        let truncated: &[&[u8]] = &[
+            &[0x01],
+            &[0x4b, 0xaa],
+            &[0x4c],
+            &[0x4c, 0x05, 0xaa],
+            &[0x4d, 0x01],
+            &[0x4d, 0x00, 0x01, 0xaa],
+            &[0x4e, 0x00, 0x00],
+            // 4 GB
+            &[0x4e, 0xff, 0xff, 0xff, 0xff, 0xaa],
+        ];
+        for data in truncated {
+            let script = Script(data.to_vec());
+            let instructions = script.instructions().collect::<Vec<_>>();
+            assert_eq!(instructions, [Err(ScriptError::UnexpectedEnd)], "{:02x?}", data);
+        }
+
+        // iteration stops after the error
+        let script = Script(vec![0x76, 0x4c, 0x05, 0xaa, 0x76]);
+        let instructions = script.instructions().collect::<Vec<_>>();
+        assert_eq!(instructions, [Ok(Instruction::Op(Opcode::OP_DUP)), Err(ScriptError::UnexpectedEnd)]);
+    }
+
+    #[test]
+    fn standard_size() {
+        let op_return = |len| ScriptBuilder::new().push_opcode(0x6a).push_slice(&vec![0xaa; len]).into_script();
+        assert_eq!(op_return(80).0.len(), 83);
+        assert!(op_return(80).is_standard_size());
+        assert!(!op_return(81).is_standard_size());
+
+        assert!(Script(vec![0x51; 10_000]).is_standard_size());
+        assert!(!Script(vec![0x51; 10_001]).is_standard_size());
+    }
+
+    #[test]
+    fn provably_unspendable() {
+        assert!(Script(vec![0x6a]).is_provably_unspendable());
+        assert!(Script(vec![0x6a, 0x02, 0xab, 0xcd]).is_provably_unspendable());
+
+        let p2pkh = ScriptBuilder::new().push_opcode(0x76).push_opcode(0xa9).push_slice(&[0x11; 20]).push_opcode(0x88).push_opcode(0xac).into_script();
+        assert!(!p2pkh.is_provably_unspendable());
+        // OP_RETURN later in the script only fails when executed
+        assert!(!Script(vec![0x51, 0x6a]).is_provably_unspendable());
+
+        assert!(!Script(vec![0x51; 10_000]).is_provably_unspendable());
+        assert!(Script(vec![0x51; 10_001]).is_provably_unspendable());
+    }
+
+    #[test]
+    fn p2pk_pubkey() {
+        // spent by the first bitcoin transfer
+        let uncompressed = Script(crate::hex::decode(crate::test_vectors::TX_170_SPENT_SCRIPT).unwrap());
+        assert_eq!(uncompressed.p2pk_pubkey(), Some(&uncompressed.0[1..66]));
+        assert_eq!(uncompressed.p2pk_pubkey().unwrap()[0], 0x04);
+
+        let key = crate::hex::decode("025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeee6357").unwrap();
+        let compressed = ScriptBuilder::new().push_slice(&key).push_opcode(0xac).into_script();
+        assert_eq!(compressed.p2pk_pubkey(), Some(&*key));
+
+        let mut invalid_prefix = key.clone();
+        invalid_prefix[0] = 0x04;
+        let script = ScriptBuilder::new().push_slice(&invalid_prefix).push_opcode(0xac).into_script();
+        assert_eq!(script.p2pk_pubkey(), None);
+
+        let without_checksig = ScriptBuilder::new().push_slice(&key).into_script();
+        assert_eq!(without_checksig.p2pk_pubkey(), None);
+        assert_eq!(Script(Vec::new()).p2pk_pubkey(), None);
+    }
+
+    #[test]
+    fn script_hashes() {
+        // P2SH-P2WPKH redeem script and output from BIP143
+        let redeem_script = Script(crate::hex::decode("001479091972186c449eb1ded22b78e40d009bdf0089").unwrap());
+        assert_eq!(redeem_script.script_hash().to_string(), "4733f37cf4db86fbc2efed2500b4f4e49f312023");
+        let p2sh = ScriptBuilder::new()
+            .push_opcode(0xa9)
+            .push_slice(&redeem_script.script_hash().0)
+            .push_opcode(0x87)
+            .into_script();
+        assert_eq!(p2sh.0, crate::hex::decode("a9144733f37cf4db86fbc2efed2500b4f4e49f31202387").unwrap());
+
+        // P2WSH of `<G> OP_CHECKSIG` from BIP173
+        let witness_script = Script(crate::hex::decode("210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ac").unwrap());
+        let p2wsh = Script(crate::hex::decode("00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262").unwrap());
+        assert_eq!(p2wsh.script_type(), ScriptType::P2wsh);
+        let (version, program) = p2wsh.witness_program().unwrap();
+        assert_eq!(version, 0);
+        assert_eq!(witness_script.witness_script_hash().to_internal_bytes(), program);
+        let mut reversed = program.to_vec();
+        reversed.reverse();
+        assert_eq!(witness_script.witness_script_hash().to_string(), crate::hex::encode(&reversed));
+        let built = ScriptBuilder::new().push_opcode(0x00).push_slice(&witness_script.witness_script_hash().to_internal_bytes()).into_script();
+        assert_eq!(built, p2wsh);
+    }
+
+    #[test]
+    fn witness_program() {
+        let script = |opcode: u8, len: usize| {
+            let mut data = vec![opcode, len as u8];
+            data.extend(vec![0xab; len]);
+            Script(data)
+        };
+
+        // P2TR
+        let p2tr = script(0x51, 32);
+        assert_eq!(p2tr.witness_program(), Some((1, &[0xab; 32][..])));
+        assert_eq!(script(0x00, 20).witness_program(), Some((0, &[0xab; 20][..])));
+
+        // programs must have 2 to 40 bytes
+        assert_eq!(script(0x52, 2).witness_program(), Some((2, &[0xab; 2][..])));
+        assert_eq!(script(0x51, 1).witness_program(), None);
+        assert_eq!(script(0x60, 40).witness_program(), Some((16, &[0xab; 40][..])));
+        assert_eq!(script(0x60, 41).witness_program(), None);
+
+        // OP_1NEGATE and OP_RESERVED are not versions
+        assert_eq!(script(0x4f, 32).witness_program(), None);
+        assert_eq!(script(0x50, 32).witness_program(), None);
+
+        let mut wrong_len = p2tr;
+        wrong_len.0.pop();
+        assert_eq!(wrong_len.witness_program(), None);
+        assert_eq!(Script(Vec::new()).witness_program(), None);
+    }
+
+    #[test]
+    fn multisig_2_of_3() {
+        // witness script of transaction 5df912fda4becb1c29e928bec8d64d93e9ba8efa9b5b405bd683c86fd2c65667
+        let keys = [
+            "0375e00eb72e29da82b89367947f29ef34afb75e8654f6ea368e0acdfd92976b7c",
+            "03a1b26313f430c4b15bb1fdce663207659d8cac749a0e53d70eff01874496feff",
+            "03c96d495bfdd5ba4145e3e046fee45e84a8a48ad05bd8dbb395c011a32cf9f880",
+        ];
+        let hex = format!("5221{}21{}21{}53ae", keys[0], keys[1], keys[2]);
+        let script = Script(crate::hex::decode(&hex).unwrap());
+
+        let multisig = script.parse_multisig().unwrap();
+        assert_eq!(multisig.required, 2);
+        assert_eq!(multisig.total(), 3);
+        for (key, expected) in multisig.public_keys.iter().zip(&keys) {
+            assert_eq!(crate::hex::encode(key), *expected);
+        }
+        assert_eq!(script.script_type(), ScriptType::NonStandard);
+
+        let p2wsh = ScriptBuilder::new().push_opcode(0x00).push_slice(&script.witness_script_hash().to_internal_bytes()).into_script();
+        assert_eq!(p2wsh.address(crate::Network::Mainnet).unwrap(), "bc1qwqdg6squsna38e46795at95yu9atm8azzmyvckulcc7kytlcckxswvvzej");
+
+        let modified = |offset: usize, opcode: u8| {
+            let mut script = script.clone();
+            script.0[offset] = opcode;
+            script
+        };
+        let last = script.0.len() - 1;
+        // four keys declared
+        assert_eq!(modified(last - 1, 0x54).parse_multisig(), None);
+        // four signatures required
+        assert_eq!(modified(0, 0x54).parse_multisig(), None);
+        // zero signatures required
+        assert_eq!(modified(0, 0x00).parse_multisig(), None);
+        // OP_CHECKMULTISIGVERIFY
+        assert_eq!(modified(last, 0xaf).parse_multisig(), None);
+        // invalid key prefix
+        assert_eq!(modified(2, 0x05).parse_multisig(), None);
+    }
+
+    #[test]
+    fn minimal_pushes() {
+        let cases: &[(&[u8], bool)] = &[
+            (&[], true),
+            // 5 bytes pushed directly and using OP_PUSHDATA1
+            (&[0x05, 1, 2, 3, 4, 5, 0x87], true),
+            (&[0x4c, 0x05, 1, 2, 3, 4, 5, 0x87], false),
+            // OP_PUSHDATA2 and OP_PUSHDATA4 for 5 bytes
+            (&[0x4d, 0x05, 0x00, 1, 2, 3, 4, 5], false),
+            (&[0x4e, 0x05, 0x00, 0x00, 0x00, 1, 2, 3, 4, 5], false),
+            // empty push using OP_PUSHBYTES_0 is OP_0 itself
+            (&[0x00], true),
+            (&[0x4c, 0x00], false),
+            // small numbers
+            (&[0x51], true),
+            (&[0x01, 0x01], false),
+            (&[0x01, 0x10], false),
+            (&[0x01, 0x11], true),
+            (&[0x4f], true),
+            (&[0x01, 0x81], false),
+            (&[0x01, 0x00], true),
+            // truncated push
+            (&[0x05, 1, 2], false),
+        ];
+        for &(script, expected) in cases {
+            assert_eq!(Script(script.to_vec()).uses_minimal_pushes(), expected, "{:02x?}", script);
+        }
+
+        let mut pushdata1 = vec![0x4c, 0x4c];
+        pushdata1.extend_from_slice(&[0xaa; 0x4c]);
+        assert!(Script(pushdata1).uses_minimal_pushes());
+        let mut pushdata1 = vec![0x4c, 0x4b];
+        pushdata1.extend_from_slice(&[0xaa; 0x4b]);
+        assert!(!Script(pushdata1).uses_minimal_pushes());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn deserialize_counted() {
+        let data = [0x05, 1, 2, 3, 4, 5, 0xff];
+        let (script, count) = Script::deserialize_counted(&mut &data[..]).unwrap();
+        assert_eq!(script.0, [1, 2, 3, 4, 5]);
+        assert_eq!(count, 6);
+
+        // non-minimal length prefix is counted as encoded
+        let data = [0xfd, 0x05, 0x00, 1, 2, 3, 4, 5];
+        assert_eq!(Script::deserialize_counted(&mut &data[..]).unwrap().1, 8);
+
+        let limits = Limits {
+            max_script_len: 4,
+            ..Limits::consensus()
+        };
+        let error = Script::deserialize_counted_with_limits(&mut &data[..], &limits).unwrap_err();
+        assert!(matches!(error, ParseError::ScriptTooLong { len: 5 }), "{:?}", error);
+    }
+}
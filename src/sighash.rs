@@ -0,0 +1,200 @@
+//! Computation of hashes signed by transaction signatures.
+
+use byteorder::{LE, WriteBytesExt};
+use std::io;
+use std::io::Write;
+use crate::amount::Amount;
+use crate::hash::{Hash256, Hash256Engine};
+use crate::script::Script;
+use crate::transaction::Transaction;
+use crate::varint::serialize_varint;
+
+/// Signs all inputs and outputs.
+pub const SIGHASH_ALL: u32 = 0x01;
+/// Signs all inputs and no outputs.
+pub const SIGHASH_NONE: u32 = 0x02;
+/// Signs all inputs and the output with the same index as the signed input.
+pub const SIGHASH_SINGLE: u32 = 0x03;
+/// Flag signing only the input being signed, combined with one of the types above.
+pub const SIGHASH_ANYONECANPAY: u32 = 0x80;
+
+impl Transaction {
+    /// Computes the legacy (pre-SegWit) signature hash of the input.
+    ///
+    /// `script_code` is used as is, removing `OP_CODESEPARATOR` is left to the caller.
+    /// `SIGHASH_SINGLE` without matching output returns hash `1`, as in Bitcoin Core.
+    /// Returns `None` if `input_index` is out of range.
+    pub fn signature_hash(&self, input_index: usize, script_code: &Script, sighash_type: u32) -> Option<Hash256> {
+        self.input(input_index)?;
+
+        if sighash_type & 0x1f == SIGHASH_SINGLE && input_index >= self.outputs.len() {
+            let mut one = [0; 32];
+            one[0] = 1;
+            return Some(Hash256(one));
+        }
+
+        // Serializing directly into the engine avoids copying the transaction.
+        Some(hash_serialized(|writer| self.serialize_for_signature(writer, input_index, script_code, sighash_type)))
+    }
+
+    /// Computes the signature hash of SegWit v0 input as defined in BIP143.
+    ///
+    /// `amount` is the value of the output spent by the input, `script_code` is
+    /// serialized including its length. Returns `None` if `input_index` is out of range.
+    pub fn segwit_signature_hash(&self, input_index: usize, script_code: &Script, amount: Amount, sighash_type: u32) -> Option<Hash256> {
+        let input = self.input(input_index)?;
+        let base_type = sighash_type & 0x1f;
+        let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+        let signs_all_outputs = base_type != SIGHASH_SINGLE && base_type != SIGHASH_NONE;
+
+        let hash_prevouts = if anyone_can_pay {
+            Hash256([0; 32])
+        } else {
+            hash_serialized(|writer| self.inputs.iter().try_for_each(|input| input.outpoint.serialize(writer)))
+        };
+        let hash_sequence = if anyone_can_pay || !signs_all_outputs {
+            Hash256([0; 32])
+        } else {
+            hash_serialized(|writer| self.inputs.iter().try_for_each(|input| writer.write_u32::<LE>(input.sequence)))
+        };
+        let hash_outputs = if signs_all_outputs {
+            hash_serialized(|writer| self.outputs.iter().try_for_each(|output| output.serialize(writer)))
+        } else if let (SIGHASH_SINGLE, Some(output)) = (base_type, self.output(input_index)) {
+            hash_serialized(|writer| output.serialize(writer))
+        } else {
+            Hash256([0; 32])
+        };
+
+        Some(hash_serialized(|writer| {
+            writer.write_u32::<LE>(self.version)?;
+            hash_prevouts.serialize(writer)?;
+            hash_sequence.serialize(writer)?;
+            input.outpoint.serialize(writer)?;
+            script_code.serialize(writer)?;
+            writer.write_u64::<LE>(amount.0)?;
+            writer.write_u32::<LE>(input.sequence)?;
+            hash_outputs.serialize(writer)?;
+            writer.write_u32::<LE>(self.lock_time)?;
+            writer.write_u32::<LE>(sighash_type)
+        }))
+    }
+
+    /// Serializes the transaction modified according to the legacy signature hash type.
+    fn serialize_for_signature<W: Write>(&self, writer: &mut W, input_index: usize, script_code: &Script, sighash_type: u32) -> io::Result<()> {
+        let base_type = sighash_type & 0x1f;
+        let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+
+        writer.write_u32::<LE>(self.version)?;
+
+        let input_count = if anyone_can_pay { 1 } else { self.inputs.len() };
+        serialize_varint(writer, input_count as u64)?;
+        for (i, input) in self.inputs.iter().enumerate() {
+            if anyone_can_pay && i != input_index {
+                continue;
+            }
+
+            input.outpoint.serialize(writer)?;
+            if i == input_index {
+                script_code.serialize(writer)?;
+                writer.write_u32::<LE>(input.sequence)?;
+            } else {
+                // empty script
+                writer.write_u8(0)?;
+                // Sequences of other inputs are not signed if the outputs aren't signed.
+                let sequence = match base_type {
+                    SIGHASH_NONE | SIGHASH_SINGLE => 0,
+                    _ => input.sequence,
+                };
+                writer.write_u32::<LE>(sequence)?;
+            }
+        }
+
+        match base_type {
+            SIGHASH_NONE => serialize_varint(writer, 0)?,
+            SIGHASH_SINGLE => {
+                serialize_varint(writer, input_index as u64 + 1)?;
+                for _ in 0..input_index {
+                    // Outputs before the signed one have value -1 and empty script.
+                    writer.write_u64::<LE>(u64::MAX)?;
+                    writer.write_u8(0)?;
+                }
+                self.outputs[input_index].serialize(writer)?;
+            },
+            _ => {
+                serialize_varint(writer, self.outputs.len() as u64)?;
+                for output in &self.outputs {
+                    output.serialize(writer)?;
+                }
+            },
+        }
+
+        writer.write_u32::<LE>(self.lock_time)?;
+        writer.write_u32::<LE>(sighash_type)
+    }
+}
+
+/// Computes double SHA256 of the data written by `serialize`.
+fn hash_serialized<F: FnOnce(&mut Hash256Engine) -> io::Result<()>>(serialize: F) -> Hash256 {
+    let mut engine = Hash256Engine::new();
+    serialize(&mut engine).expect("hashing never fails");
+    engine.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex;
+    use crate::test_vectors::*;
+
+    fn hash(hex_str: &str) -> Hash256 {
+        let mut bytes = [0; 32];
+        bytes.copy_from_slice(&hex::decode(hex_str).unwrap());
+        Hash256(bytes)
+    }
+
+    #[test]
+    fn legacy() {
+        let transaction = Transaction::from_hex(TX_170).unwrap();
+        let script_code = Script(hex::decode(TX_170_SPENT_SCRIPT).unwrap());
+        // expected hashes are in the order they are signed in, not reversed
+        let cases = [
+            (SIGHASH_ALL, "7a05c6145f10101e9d6325494245adf1297d80f8f38d4d576d57cdba220bcb19"),
+            (SIGHASH_NONE, "0c75c3ac059ee8e19758c58c757d88bcb18d447517ce4d1c3b5a6b7183b41698"),
+            (SIGHASH_SINGLE, "2c836064b405a0d6658da729df4b73667d864c2861601a6d1cfc4264556fc203"),
+            (SIGHASH_ALL | SIGHASH_ANYONECANPAY, "45692ee72fe2285c88b2339c47d2f7d01f0b130494fd42be524a23672421d3f9"),
+        ];
+
+        for &(sighash_type, expected) in &cases {
+            assert_eq!(transaction.signature_hash(0, &script_code, sighash_type), Some(hash(expected)), "type {:#x}", sighash_type);
+        }
+    }
+
+    #[test]
+    fn legacy_single_without_output() {
+        let mut transaction = Transaction::from_hex(TX_170).unwrap();
+        transaction.outputs.clear();
+        let mut one = [0; 32];
+        one[0] = 1;
+
+        assert_eq!(transaction.signature_hash(0, &Script(Vec::new()), SIGHASH_SINGLE), Some(Hash256(one)));
+    }
+
+    #[test]
+    fn out_of_range() {
+        let transaction = Transaction::from_hex(TX_170).unwrap();
+        let script_code = Script(Vec::new());
+
+        assert_eq!(transaction.signature_hash(1, &script_code, SIGHASH_ALL), None);
+        assert_eq!(transaction.segwit_signature_hash(1, &script_code, Amount(0), SIGHASH_ALL), None);
+    }
+
+    /// Native P2WPKH example from BIP143.
+    #[test]
+    fn bip143_p2wpkh() {
+        let transaction = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        let script_code = Script(hex::decode("76a9141d0f172a0ecb48aee1be1f2687d2963ae33f71a188ac").unwrap());
+        let sighash = transaction.segwit_signature_hash(1, &script_code, Amount(600_000_000), SIGHASH_ALL);
+
+        assert_eq!(sighash, Some(hash("c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670")));
+    }
+}
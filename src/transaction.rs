@@ -0,0 +1,2139 @@
+#[cfg(feature = "std")]
+use byteorder::{LE, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::cmp::Ordering;
+use crate::error::ParseError;
+use crate::amount::Amount;
+#[cfg(feature = "std")]
+use crate::limits::{self, Limits, SizeHint};
+#[cfg(feature = "std")]
+use crate::reader::{CountingReader, LimitedReader};
+#[cfg(feature = "std")]
+use crate::hex;
+#[cfg(feature = "std")]
+use crate::varint::{deserialize_varint, serialize_varint};
+#[cfg(feature = "tokio")]
+use crate::varint::deserialize_varint_async;
+use crate::varint::varint_len;
+use crate::prelude::*;
+use crate::hash::Hash256;
+#[cfg(feature = "std")]
+use crate::hash::Hash256Engine;
+use crate::script::{self, Script, ScriptType, Instruction};
+use crate::witness::{Witness, TaprootSpend};
+use crate::address::Network;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Maximum weight of a block, so also of a transaction. (BIP141)
+#[cfg(feature = "std")]
+const MAX_WEIGHT: usize = 4_000_000;
+
+/// Defines "outpoint" - output of previous transaction being consumed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Outpoint {
+    /// ID of previous transaction
+    pub txid: Hash256,
+    /// Which output of the previous transaction is being consumed, little endian on the wire.
+    pub index: u32,
+}
+
+#[cfg(feature = "std")]
+impl Outpoint {
+    /// Deserializes the outpoint from the blockchain data
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let txid = Hash256::deserialize(reader)?;
+        let index = reader.read_u32::<LE>()?;
+
+        Ok(Outpoint {
+            txid,
+            index,
+        })
+    }
+
+    /// Serializes the outpoint the same way it's stored in the blockchain
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.txid.serialize(writer)?;
+        writer.write_u32::<LE>(self.index)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Outpoint {
+    /// Deserializes the outpoint from an async reader
+    pub async fn deserialize_async<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self, ParseError> {
+        let txid = Hash256::deserialize_async(reader).await?;
+        let index = reader.read_u32_le().await?;
+
+        Ok(Outpoint {
+            txid,
+            index,
+        })
+    }
+}
+
+impl Outpoint {
+    /// Number of bytes of serialized outpoint.
+    pub const SERIALIZED_LEN: usize = 36;
+
+    /// Checks whether the outpoint doesn't refer to any output, as is the case in coinbase.
+    pub fn is_null(&self) -> bool {
+        self.txid.0 == [0; 32] && self.index == 0xFFFFFFFF
+    }
+}
+
+/// Orders outpoints as required by BIP69.
+///
+/// Txids are compared in reversed byte order, as they are displayed, then indices are compared.
+impl Ord for Outpoint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.txid.0.iter().rev().cmp(other.txid.0.iter().rev())
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+impl PartialOrd for Outpoint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Contains data about single transaction input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TxInput {
+    pub outpoint: Outpoint,
+    pub sig_script: Script,
+    /// Little endian on the wire
+    pub sequence: u32,
+}
+
+#[cfg(feature = "std")]
+impl TxInput {
+    /// Deserializes the input from the blockchain data
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        TxInput::deserialize_with_limits(reader, &Limits::consensus())
+    }
+
+    /// Deserializes the input from the blockchain data, checking given limits.
+    pub fn deserialize_with_limits<R: Read>(reader: &mut R, limits: &Limits) -> Result<Self, ParseError> {
+        let outpoint = Outpoint::deserialize(reader).map_err(ParseError::field("outpoint"))?;
+        let sig_script = Script::deserialize_with_limits(reader, limits).map_err(ParseError::field("script"))?;
+        let sequence = reader.read_u32::<LE>().map_err(ParseError::field("sequence"))?;
+
+        Ok(TxInput {
+            outpoint,
+            sig_script,
+            sequence,
+        })
+    }
+
+    /// Advances the reader past the input without storing it, checking given limits.
+    pub fn skip<R: Read>(reader: &mut R, limits: &Limits) -> Result<(), ParseError> {
+        Outpoint::deserialize(reader).map_err(ParseError::field("outpoint"))?;
+        Script::skip(reader, limits).map_err(ParseError::field("script"))?;
+        reader.read_u32::<LE>().map_err(ParseError::field("sequence"))?;
+        Ok(())
+    }
+
+    /// Serializes the input the same way it's stored in the blockchain
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.outpoint.serialize(writer)?;
+        self.sig_script.serialize(writer)?;
+        writer.write_u32::<LE>(self.sequence)
+    }
+}
+
+/// Contains data about single transaction output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TxOutput {
+    /// Little endian on the wire
+    pub satoshis: Amount,
+    pub verify_script: Script,
+}
+
+#[cfg(feature = "std")]
+impl TxOutput {
+    /// Deserializes the output from the blockchain data
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        TxOutput::deserialize_with_limits(reader, &Limits::consensus())
+    }
+
+    /// Deserializes the output from the blockchain data, checking given limits.
+    ///
+    /// Pass `Limits::strict()` to reject values above `Amount::MAX_MONEY`.
+    pub fn deserialize_with_limits<R: Read>(reader: &mut R, limits: &Limits) -> Result<Self, ParseError> {
+        let satoshis = Amount(reader.read_u64::<LE>().map_err(ParseError::field("value"))?);
+        if satoshis > limits.max_output_value {
+            return Err(ParseError::InvalidAmount(satoshis.0));
+        }
+        let verify_script = Script::deserialize_with_limits(reader, limits).map_err(ParseError::field("script"))?;
+
+        Ok(TxOutput {
+            satoshis,
+            verify_script
+        })
+    }
+
+    /// Advances the reader past the output without storing it, checking given limits.
+    pub fn skip<R: Read>(reader: &mut R, limits: &Limits) -> Result<(), ParseError> {
+        let satoshis = reader.read_u64::<LE>().map_err(ParseError::field("value"))?;
+        if satoshis > limits.max_output_value.0 {
+            return Err(ParseError::InvalidAmount(satoshis));
+        }
+        Script::skip(reader, limits).map_err(ParseError::field("script"))
+    }
+
+    /// Serializes the output the same way it's stored in the blockchain
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_u64::<LE>(self.satoshis.0)?;
+        self.verify_script.serialize(writer)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl TxInput {
+    /// Deserializes the input from an async reader
+    pub async fn deserialize_async<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self, ParseError> {
+        TxInput::deserialize_with_limits_async(reader, &Limits::consensus()).await
+    }
+
+    /// Deserializes the input from an async reader, checking given limits.
+    pub async fn deserialize_with_limits_async<R: AsyncRead + Unpin>(reader: &mut R, limits: &Limits) -> Result<Self, ParseError> {
+        let outpoint = Outpoint::deserialize_async(reader).await.map_err(ParseError::field("outpoint"))?;
+        let sig_script = Script::deserialize_with_limits_async(reader, limits).await.map_err(ParseError::field("script"))?;
+        let sequence = reader.read_u32_le().await.map_err(ParseError::field("sequence"))?;
+
+        Ok(TxInput {
+            outpoint,
+            sig_script,
+            sequence,
+        })
+    }
+}
+
+impl TxInput {
+    /// Returns the number of bytes `serialize` writes.
+    pub fn serialized_len(&self) -> usize {
+        Outpoint::SERIALIZED_LEN + self.sig_script.serialized_len() + 4
+    }
+
+    /// Guesses the type of the spent output script from the shape of the input.
+    ///
+    /// This is only a heuristic: the input doesn't commit to the spent script,
+    /// so unusual scripts can be misclassified. `witness` is the witness of this
+    /// input, if the transaction has any. Returns `None` if the shape doesn't
+    /// match any known type, which includes Taproot spends.
+    pub fn infer_spent_script_type(&self, witness: Option<&Witness>) -> Option<ScriptType> {
+        if witness.is_some_and(looks_like_taproot) {
+            return None;
+        }
+        let witness = witness.map(|witness| &witness.0[..]).unwrap_or(&[]);
+        let mut pushes = Vec::new();
+        for instruction in self.sig_script.instructions() {
+            match instruction {
+                Ok(Instruction::PushBytes(data)) => pushes.push(data),
+                _ => return None,
+            }
+        }
+
+        match (&pushes[..], witness) {
+            // Native SegWit: <signature> <public key>
+            ([], [_, public_key]) if public_key.len() == 33 => Some(ScriptType::P2wpkh),
+            // Native SegWit: <inputs>... <witness script>
+            ([], [.., _]) => Some(ScriptType::P2wsh),
+            ([], _) => None,
+            // Nested SegWit: <witness program> in place of redeem script
+            ([redeem_script], [_, ..]) if Script(redeem_script.to_vec()).witness_program().is_some() => Some(ScriptType::P2sh),
+            ([signature], []) if is_signature(signature) => Some(ScriptType::P2pk),
+            ([signature, public_key], []) if is_signature(signature) && script::is_public_key(public_key) => Some(ScriptType::P2pkh),
+            // <inputs>... <redeem script>, the script should end with an opcode such as `OP_CHECKSIG`
+            ([.., redeem_script], []) if ends_with_opcode(redeem_script) => Some(ScriptType::P2sh),
+            _ => None,
+        }
+    }
+}
+
+/// Constructs transactions input by input and output by output.
+///
+/// The transaction is built without witnesses, they can be added to `witnesses`
+/// afterwards. Note that transaction without inputs doesn't deserialize back
+/// the same, see `Transaction::deserialize_with_limits`.
+#[derive(Debug, Clone)]
+pub struct TransactionBuilder {
+    transaction: Transaction,
+}
+
+impl TransactionBuilder {
+    /// Creates the builder of transaction with given version, no inputs, no outputs and zero lock time.
+    pub fn new(version: u32) -> Self {
+        TransactionBuilder {
+            transaction: Transaction {
+                version,
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                lock_time: 0,
+                witnesses: Vec::new(),
+            },
+        }
+    }
+
+    /// Appends the input spending `outpoint`.
+    pub fn add_input(mut self, outpoint: Outpoint, sig_script: Script, sequence: u32) -> Self {
+        self.transaction.inputs.push(TxInput {
+            outpoint,
+            sig_script,
+            sequence,
+        });
+        self
+    }
+
+    /// Appends the output.
+    pub fn add_output(mut self, satoshis: Amount, verify_script: Script) -> Self {
+        self.transaction.outputs.push(TxOutput {
+            satoshis,
+            verify_script,
+        });
+        self
+    }
+
+    /// Sets the raw lock time.
+    pub fn lock_time(mut self, lock_time: u32) -> Self {
+        self.transaction.lock_time = lock_time;
+        self
+    }
+
+    /// Returns the constructed transaction.
+    pub fn build(self) -> Transaction {
+        self.transaction
+    }
+}
+
+/// Returns the error of failed validation, logging it if `tracing` is enabled.
+fn invalid(error: ParseError) -> ParseError {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(%error, "transaction is invalid");
+    error
+}
+
+/// Checks whether the data looks like DER signature followed by sighash type.
+fn is_signature(data: &[u8]) -> bool {
+    data.len() >= 9 && data.len() <= 73 && data[0] == 0x30
+}
+
+/// Checks whether the witness is a Schnorr signature or Tapscript spend.
+fn looks_like_taproot(witness: &Witness) -> bool {
+    match witness.taproot_spend() {
+        Some(TaprootSpend::KeyPath { signature, .. }) => signature.len() == 64 || signature.len() == 65,
+        Some(TaprootSpend::ScriptPath { control_block, .. }) => control_block.leaf_version() == 0xc0,
+        None => false,
+    }
+}
+
+/// Checks whether the data parses as a script with the last instruction not being a push.
+fn ends_with_opcode(data: &[u8]) -> bool {
+    let script = Script(data.to_vec());
+    let mut last = None;
+    for instruction in script.instructions() {
+        match instruction {
+            Ok(instruction) => last = Some(instruction),
+            Err(_) => return false,
+        }
+    }
+    matches!(last, Some(Instruction::Op(_)))
+}
+
+#[cfg(feature = "tokio")]
+impl TxOutput {
+    /// Deserializes the output from an async reader
+    pub async fn deserialize_async<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self, ParseError> {
+        TxOutput::deserialize_with_limits_async(reader, &Limits::consensus()).await
+    }
+
+    /// Deserializes the output from an async reader, checking given limits.
+    pub async fn deserialize_with_limits_async<R: AsyncRead + Unpin>(reader: &mut R, limits: &Limits) -> Result<Self, ParseError> {
+        let satoshis = Amount(reader.read_u64_le().await.map_err(ParseError::field("value"))?);
+        if satoshis > limits.max_output_value {
+            return Err(ParseError::InvalidAmount(satoshis.0));
+        }
+        let verify_script = Script::deserialize_with_limits_async(reader, limits).await.map_err(ParseError::field("script"))?;
+
+        Ok(TxOutput {
+            satoshis,
+            verify_script
+        })
+    }
+}
+
+impl TxOutput {
+    /// Returns the number of bytes `serialize` writes.
+    pub fn serialized_len(&self) -> usize {
+        8 + self.verify_script.serialized_len()
+    }
+
+    /// Compares the outputs as required by BIP69: by value, then by script bytes.
+    pub fn bip69_cmp(&self, other: &Self) -> Ordering {
+        self.satoshis.cmp(&other.satoshis)
+            .then_with(|| self.verify_script.0.cmp(&other.verify_script.0))
+    }
+
+    /// Returns the value below which the output is dust at given fee rate in satoshis per kB.
+    ///
+    /// This follows Bitcoin Core: the threshold is the fee for the output and
+    /// an input spending it, which is assumed to be 148 bytes (P2PKH) or 67 vbytes
+    /// for witness programs. Provably unspendable outputs are never dust.
+    /// The threshold saturates for absurdly high fee rates instead of overflowing.
+    pub fn dust_threshold(&self, dust_relay_fee_per_kb: u64) -> Amount {
+        if self.verify_script.is_provably_unspendable() {
+            return Amount::ZERO;
+        }
+
+        let spend_len = if self.verify_script.witness_program().is_some() {
+            // outpoint, empty script, 107 bytes of witness discounted to a quarter, sequence
+            32 + 4 + 1 + 107 / 4 + 4
+        } else {
+            // outpoint, 107 bytes of script with its length, sequence
+            32 + 4 + 1 + 107 + 4
+        };
+        Amount((self.serialized_len() as u64 + spend_len).saturating_mul(dust_relay_fee_per_kb) / 1000)
+    }
+
+    /// Checks whether the output is too small to be worth spending at given fee rate.
+    ///
+    /// Bitcoin Core uses 3000 satoshis per kB, which is three times the minimum relay fee,
+    /// making P2PKH outputs below 546 and P2WPKH outputs below 294 satoshis dust.
+    pub fn is_dust(&self, dust_relay_fee_per_kb: u64) -> bool {
+        self.satoshis < self.dust_threshold(dust_relay_fee_per_kb)
+    }
+}
+
+/// Interpretation of the lock time of a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockTime {
+    /// The transaction is not locked.
+    None,
+    /// The transaction can't be included in a block below this height.
+    Height(u32),
+    /// The transaction can't be included in a block with timestamp below this Unix time.
+    Time(u32),
+}
+
+impl LockTime {
+    /// Values at or above this are interpreted as Unix timestamps, below it as block heights.
+    pub const THRESHOLD: u32 = 500_000_000;
+
+    /// Interprets the raw lock time.
+    pub fn from_raw(lock_time: u32) -> Self {
+        match lock_time {
+            0 => LockTime::None,
+            height if height < LockTime::THRESHOLD => LockTime::Height(height),
+            time => LockTime::Time(time),
+        }
+    }
+}
+
+/// Input of a transaction together with its witness, returned by `Transaction::input_infos`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputInfo<'a> {
+    pub outpoint: &'a Outpoint,
+    pub sequence: u32,
+    /// `None` if the transaction was serialized without witness
+    pub witness: Option<&'a Witness>,
+}
+
+/// Interpreted output of a transaction, returned by `Transaction::output_infos`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputInfo {
+    pub satoshis: Amount,
+    pub script_type: ScriptType,
+    /// `None` if the script doesn't have an address
+    pub address: Option<String>,
+}
+
+/// Fields of transaction which can be read without parsing the scripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionHeader {
+    pub version: u32,
+    /// Whether the transaction is serialized with witness
+    pub has_witness: bool,
+    pub input_count: u64,
+    pub output_count: u64,
+}
+
+/// Fields of transaction returned by `Transaction::deserialize_streaming`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamedTransaction {
+    pub header: TransactionHeader,
+    pub lock_time: u32,
+}
+
+/// Contains data about single transaction
+///
+/// All integers are serialized as little endian, hashes as raw bytes in the
+/// order they are stored in `Hash256`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Transaction {
+    /// Little endian on the wire
+    pub version: u32,
+    pub inputs: Vec<TxInput>,
+    pub outputs: Vec<TxOutput>,
+    /// Little endian on the wire
+    pub lock_time: u32,
+    /// Witness of each input, empty if the transaction was serialized without witness.
+    ///
+    /// SegWit transaction may have all the stacks empty. Bitcoin Core rejects
+    /// such transaction as having superfluous witness, but it's accepted here
+    /// and serializes back the same, marker included.
+    pub witnesses: Vec<Witness>,
+}
+
+#[cfg(feature = "std")]
+impl Transaction {
+    /// Deserializes the transaction from the blockchain data
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        Transaction::deserialize_with_limits(reader, &Limits::consensus())
+    }
+
+    /// Deserializes the transaction from the blockchain data and validates it.
+    ///
+    /// See `validate` for the checks performed.
+    pub fn deserialize_strict<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let transaction = Transaction::deserialize(reader)?;
+        transaction.validate()?;
+        Ok(transaction)
+    }
+
+    /// Deserializes the transaction from the blockchain data, checking given limits.
+    ///
+    /// Errors are wrapped in `ParseError::Context` describing the field which
+    /// failed to parse, such as `output 3: script: unexpected end of data`.
+    pub fn deserialize_with_limits<R: Read>(reader: &mut R, limits: &Limits) -> Result<Self, ParseError> {
+        Transaction::deserialize_collecting(reader, limits, &SizeHint::default(), true)
+    }
+
+    /// Deserializes the transaction, allocating memory for inputs and outputs according to the hint.
+    pub fn deserialize_with_hint<R: Read>(reader: &mut R, limits: &Limits, hint: &SizeHint) -> Result<Self, ParseError> {
+        Transaction::deserialize_collecting(reader, limits, hint, true)
+    }
+
+    /// Deserializes the transaction, discarding witnesses.
+    ///
+    /// The witness is still read, so the reader ends up at the same position as
+    /// with `deserialize`, but `witnesses` is left empty. This saves memory when
+    /// only txids or the legacy view of transactions are needed.
+    pub fn deserialize_stripped<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        Transaction::deserialize_stripped_with_limits(reader, &Limits::consensus())
+    }
+
+    /// Same as `deserialize_stripped`, checking given limits.
+    pub fn deserialize_stripped_with_limits<R: Read>(reader: &mut R, limits: &Limits) -> Result<Self, ParseError> {
+        Transaction::deserialize_collecting(reader, limits, &SizeHint::default(), false)
+    }
+
+    /// Deserializes the transaction, collecting streamed parts into vectors.
+    fn deserialize_collecting<R: Read>(reader: &mut R, limits: &Limits, hint: &SizeHint, keep_witnesses: bool) -> Result<Self, ParseError> {
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+        let mut witnesses = Vec::new();
+
+        // The counts are checked against limits before the callbacks are called.
+        let streamed = Transaction::deserialize_streaming_counted(
+            reader,
+            limits,
+            |input, count| {
+                if inputs.is_empty() {
+                    inputs.reserve_exact(limits::preallocation_hinted(count, hint.inputs));
+                }
+                inputs.push(input);
+            },
+            |output, count| {
+                if outputs.is_empty() {
+                    outputs.reserve_exact(limits::preallocation_hinted(count, hint.outputs));
+                }
+                outputs.push(output);
+            },
+            |witness, count| if keep_witnesses {
+                if witnesses.is_empty() {
+                    witnesses.reserve_exact(limits::preallocation_hinted(count, hint.inputs));
+                }
+                witnesses.push(witness);
+            },
+        )?;
+
+        Ok(Transaction {
+            version: streamed.header.version,
+            inputs,
+            outputs,
+            lock_time: streamed.lock_time,
+            witnesses,
+        })
+    }
+
+    /// Deserializes the transaction passing each input, output and witness to the callbacks.
+    ///
+    /// This avoids keeping all of them in memory at once. Witnesses are parsed
+    /// after all outputs, one for each input, and only if the transaction has them.
+    pub fn deserialize_streaming<R, I, O, W>(reader: &mut R, on_input: I, on_output: O, on_witness: W) -> Result<StreamedTransaction, ParseError>
+    where
+        R: Read,
+        I: FnMut(TxInput),
+        O: FnMut(TxOutput),
+        W: FnMut(Witness),
+    {
+        Transaction::deserialize_streaming_with_limits(reader, &Limits::consensus(), on_input, on_output, on_witness)
+    }
+
+    /// Same as `deserialize_streaming`, checking given limits.
+    pub fn deserialize_streaming_with_limits<R, I, O, W>(reader: &mut R, limits: &Limits, mut on_input: I, mut on_output: O, mut on_witness: W) -> Result<StreamedTransaction, ParseError>
+    where
+        R: Read,
+        I: FnMut(TxInput),
+        O: FnMut(TxOutput),
+        W: FnMut(Witness),
+    {
+        Transaction::deserialize_streaming_counted(
+            reader,
+            limits,
+            |input, _| on_input(input),
+            |output, _| on_output(output),
+            |witness, _| on_witness(witness),
+        )
+    }
+
+    /// Same as `deserialize_streaming_with_limits`, passing also the number of items to the callbacks.
+    fn deserialize_streaming_counted<R, I, O, W>(reader: &mut R, limits: &Limits, mut on_input: I, mut on_output: O, mut on_witness: W) -> Result<StreamedTransaction, ParseError>
+    where
+        R: Read,
+        I: FnMut(TxInput, u64),
+        O: FnMut(TxOutput, u64),
+        W: FnMut(Witness, u64),
+    {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("transaction").entered();
+        let mut reader = LimitedReader::new(reader, limits.max_transaction_size);
+        let reader = &mut reader;
+
+        let version = reader.read_u32::<LE>().map_err(ParseError::field("version"))?;
+        let mut input_count = deserialize_varint(reader).map_err(ParseError::field("input count"))?;
+
+        // SegWit transactions have marker 0x00 in place of input count followed
+        // by flag 0x01. (BIP144)
+        //
+        // Legacy transaction with zero inputs would look the same, so it's
+        // ambiguous. Consensus resolves this by always treating 0x00 as the
+        // marker, which is fine since transactions without inputs are invalid.
+        // Such transaction then either fails on the flag or gets parsed as
+        // SegWit, in both cases finishing without reading past the data.
+        let has_witness = input_count == 0;
+        if has_witness {
+            let flag = reader.read_u8().map_err(ParseError::field("SegWit flag"))?;
+            if flag != 1 {
+                return Err(ParseError::InvalidSegwitFlag { flag });
+            }
+            input_count = deserialize_varint(reader).map_err(ParseError::field("input count"))?;
+        }
+
+        if input_count > limits.max_inputs {
+            return Err(ParseError::TooManyInputs { count: input_count });
+        }
+        limits::checked_len(input_count)?;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(version, has_witness, input_count, "parsed header");
+        for i in 0..input_count as usize {
+            let input = TxInput::deserialize_with_limits(reader, limits).map_err(ParseError::item("input", i))?;
+            #[cfg(feature = "tracing")]
+            tracing::trace!(index = i, script_len = input.sig_script.0.len(), "parsed input");
+            on_input(input, input_count);
+        }
+
+        let output_count = deserialize_varint(reader).map_err(ParseError::field("output count"))?;
+        if output_count > limits.max_outputs {
+            return Err(ParseError::TooManyOutputs { count: output_count });
+        }
+        limits::checked_len(output_count)?;
+        for i in 0..output_count as usize {
+            let output = TxOutput::deserialize_with_limits(reader, limits).map_err(ParseError::item("output", i))?;
+            #[cfg(feature = "tracing")]
+            tracing::trace!(index = i, satoshis = output.satoshis.0, script_len = output.verify_script.0.len(), "parsed output");
+            on_output(output, output_count);
+        }
+
+        // All witness stacks may be empty, see `Transaction::witnesses`.
+        if has_witness {
+            for i in 0..input_count as usize {
+                let witness = Witness::deserialize(reader).map_err(ParseError::item("witness", i))?;
+                #[cfg(feature = "tracing")]
+                tracing::trace!(index = i, items = witness.0.len(), "parsed witness");
+                on_witness(witness, input_count);
+            }
+        }
+        let lock_time = reader.read_u32::<LE>().map_err(ParseError::field("lock time"))?;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(lock_time, size = reader.count(), "parsed transaction");
+
+        Ok(StreamedTransaction {
+            header: TransactionHeader {
+                version,
+                has_witness,
+                input_count,
+                output_count,
+            },
+            lock_time,
+        })
+    }
+
+    /// Reads the version and counts of inputs and outputs, then rewinds the reader.
+    ///
+    /// Inputs are skipped without allocating their scripts, so this is cheap even
+    /// for large transactions.
+    pub fn peek_version_and_counts<R: Read + Seek>(reader: &mut R) -> Result<TransactionHeader, ParseError> {
+        let start = reader.stream_position()?;
+        let result = Transaction::deserialize_header(reader, &Limits::consensus());
+        reader.seek(SeekFrom::Start(start))?;
+        result
+    }
+
+    /// Reads the header fields, skipping the inputs.
+    fn deserialize_header<R: Read>(reader: &mut R, limits: &Limits) -> Result<TransactionHeader, ParseError> {
+        let version = reader.read_u32::<LE>().map_err(ParseError::field("version"))?;
+        let mut input_count = deserialize_varint(reader).map_err(ParseError::field("input count"))?;
+
+        // See `deserialize_with_limits` for explanation.
+        let has_witness = input_count == 0;
+        if has_witness {
+            let flag = reader.read_u8().map_err(ParseError::field("SegWit flag"))?;
+            if flag != 1 {
+                return Err(ParseError::InvalidSegwitFlag { flag });
+            }
+            input_count = deserialize_varint(reader).map_err(ParseError::field("input count"))?;
+        }
+
+        if input_count > limits.max_inputs {
+            return Err(ParseError::TooManyInputs { count: input_count });
+        }
+        limits::checked_len(input_count)?;
+        for i in 0..input_count as usize {
+            TxInput::skip(reader, limits).map_err(ParseError::item("input", i))?;
+        }
+
+        let output_count = deserialize_varint(reader).map_err(ParseError::field("output count"))?;
+        if output_count > limits.max_outputs {
+            return Err(ParseError::TooManyOutputs { count: output_count });
+        }
+        limits::checked_len(output_count)?;
+
+        Ok(TransactionHeader {
+            version,
+            has_witness,
+            input_count,
+            output_count,
+        })
+    }
+
+    /// Deserializes the transaction, returning also the number of bytes consumed.
+    ///
+    /// This is useful for advancing a cursor in a larger buffer.
+    pub fn deserialize_counted<R: Read>(reader: &mut R) -> Result<(Self, usize), ParseError> {
+        Transaction::deserialize_counted_with_limits(reader, &Limits::consensus())
+    }
+
+    /// Deserializes the transaction checking given limits, returning also the number of bytes consumed.
+    pub fn deserialize_counted_with_limits<R: Read>(reader: &mut R, limits: &Limits) -> Result<(Self, usize), ParseError> {
+        let mut reader = CountingReader::new(reader);
+        let transaction = Transaction::deserialize_with_limits(&mut reader, limits)?;
+
+        Ok((transaction, reader.count()))
+    }
+
+    /// Deserializes the transaction which must be exactly `len` bytes long.
+    ///
+    /// Never reads more than `len` bytes and skips the trailing bytes if the
+    /// transaction is shorter, so the stream stays in sync. Both shorter and
+    /// longer transaction return `ParseError::LengthMismatch`, in the latter
+    /// case `actual` is `len` because the rest of the transaction wasn't read.
+    pub fn deserialize_exact<R: Read>(reader: &mut R, len: usize) -> Result<Self, ParseError> {
+        let mut reader = CountingReader::new(reader.take(len as u64));
+        match Transaction::deserialize(&mut reader) {
+            Ok(_) if reader.count() < len => {
+                let actual = reader.count();
+                io::copy(&mut reader, &mut io::sink())?;
+                Err(ParseError::LengthMismatch { expected: len, actual })
+            },
+            // The data ended because of the frame, not because of the reader.
+            Err(ref error) if error.is_unexpected_eof() && reader.count() == len => Err(ParseError::LengthMismatch { expected: len, actual: len }),
+            result => result,
+        }
+    }
+
+    /// Deserializes the transaction, attaching the number of bytes read to the error.
+    ///
+    /// The returned error is `ParseError::AtOffset`, pass the offset together with
+    /// the original data to `HexDump` to see where the parsing stopped.
+    pub fn deserialize_tracking_offset<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let mut reader = CountingReader::new(reader);
+        Transaction::deserialize(&mut reader).map_err(|error| error.at_offset(reader.count()))
+    }
+
+    /// Parses the transaction from hex string, ignoring optional `0x` prefix and any whitespace.
+    ///
+    /// The string must contain exactly one transaction.
+    pub fn from_hex(s: &str) -> Result<Self, ParseError> {
+        let data = hex::decode_lenient(s)?;
+        Transaction::from_bytes(&data)
+    }
+
+    /// Parses the transaction from bytes which must contain exactly one transaction.
+    ///
+    /// Use `deserialize` if the data may continue after the transaction.
+    pub fn from_bytes(mut data: &[u8]) -> Result<Self, ParseError> {
+        let transaction = Transaction::deserialize(&mut data)?;
+        if !data.is_empty() {
+            return Err(ParseError::TrailingBytes(data.len()));
+        }
+
+        Ok(transaction)
+    }
+
+    /// Serializes the transaction as hex string.
+    pub fn to_hex(&self) -> String {
+        let mut data = Vec::new();
+        self.serialize(&mut data).expect("writing to Vec never fails");
+
+        hex::encode(&data)
+    }
+
+    /// Serializes the transaction, producing the same bytes `deserialize` consumed.
+    ///
+    /// The witness is included if `witnesses` is not empty. Note that transaction
+    /// without inputs and witnesses is serialized ambiguously and `deserialize`
+    /// will interpret it as SegWit.
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.serialize_inner(writer, !self.witnesses.is_empty())
+    }
+
+    /// Serializes the transaction without witness, as used for computing txid.
+    pub fn serialize_legacy<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.serialize_inner(writer, false)
+    }
+
+    fn serialize_inner<W: Write>(&self, writer: &mut W, with_witness: bool) -> io::Result<()> {
+        writer.write_u32::<LE>(self.version)?;
+        if with_witness {
+            // marker and flag
+            writer.write_all(&[0x00, 0x01])?;
+        }
+        serialize_varint(writer, self.inputs.len() as u64)?;
+        for input in &self.inputs {
+            input.serialize(writer)?;
+        }
+        serialize_varint(writer, self.outputs.len() as u64)?;
+        for output in &self.outputs {
+            output.serialize(writer)?;
+        }
+        if with_witness {
+            for witness in &self.witnesses {
+                witness.serialize(writer)?;
+            }
+        }
+        writer.write_u32::<LE>(self.lock_time)
+    }
+
+    /// Computes ID of the transaction.
+    ///
+    /// The hash is in internal byte order, use `Hash256::to_reversed_hex` to display it.
+    pub fn txid(&self) -> Hash256 {
+        // Serializing directly into the engine avoids allocating the whole transaction.
+        let mut engine = Hash256Engine::new();
+        self.serialize_legacy(&mut engine).expect("hashing never fails");
+
+        engine.finalize()
+    }
+
+    /// Computes witness ID of the transaction, as used in the witness merkle tree. (BIP141)
+    ///
+    /// This hashes the serialization including witness, so it's the same as `txid`
+    /// for transactions without witness. Coinbase has all-zero wtxid by definition.
+    pub fn wtxid(&self) -> Hash256 {
+        if self.is_coinbase() {
+            return Hash256([0; 32]);
+        }
+
+        let mut engine = Hash256Engine::new();
+        self.serialize(&mut engine).expect("hashing never fails");
+
+        engine.finalize()
+    }
+
+    /// Checks whether any outpoint is spent by more than one input, which is invalid.
+    pub fn has_duplicate_inputs(&self) -> bool {
+        let mut outpoints = HashSet::with_capacity(self.inputs.len());
+        !self.inputs.iter().all(|input| outpoints.insert(&input.outpoint))
+    }
+
+    /// Performs cheap checks rejecting absurd transactions, such as those from untrusted peers.
+    ///
+    /// In addition to `validate` this checks that output values and their sum
+    /// don't exceed 21 million bitcoins, that no outpoint is spent twice and that
+    /// the transaction fits into a block. These are the context-free checks of
+    /// Bitcoin Core, except for coinbase script length.
+    pub fn sanity_check(&self) -> Result<(), ParseError> {
+        self.validate()?;
+
+        let mut total = Amount::ZERO;
+        for output in &self.outputs {
+            if output.satoshis > Amount::MAX_MONEY {
+                return Err(invalid(ParseError::InvalidAmount(output.satoshis.0)));
+            }
+            total = total.checked_add(output.satoshis).ok_or_else(|| invalid(ParseError::TotalOutputTooLarge))?;
+        }
+        if total > Amount::MAX_MONEY {
+            return Err(invalid(ParseError::TotalOutputTooLarge));
+        }
+
+        if self.has_duplicate_inputs() {
+            return Err(invalid(ParseError::DuplicateInputs));
+        }
+
+        let weight = self.weight();
+        if weight > MAX_WEIGHT {
+            return Err(invalid(ParseError::TransactionTooLarge { weight }));
+        }
+        Ok(())
+    }
+
+    /// Checks whether any output pays to one of the scripts, given as raw bytes.
+    ///
+    /// See `matches_filter` for checking the inputs too.
+    pub fn matches_scripts(&self, scripts: &HashSet<Vec<u8>>) -> bool {
+        self.outputs.iter().any(|output| scripts.contains(&output.verify_script.0))
+    }
+
+    /// Checks whether any input spends one of the outpoints.
+    ///
+    /// See `matches_filter` for checking the outputs too.
+    pub fn matches_outpoints(&self, outpoints: &HashSet<Outpoint>) -> bool {
+        self.inputs.iter().any(|input| outpoints.contains(&input.outpoint))
+    }
+
+    /// Checks whether the transaction pays to one of the scripts or spends one of the outpoints.
+    ///
+    /// This is how wallets scan for relevant transactions: the scripts are their
+    /// own and the outpoints were previously received.
+    pub fn matches_filter(&self, scripts: &HashSet<Vec<u8>>, outpoints: &HashSet<Outpoint>) -> bool {
+        self.matches_scripts(scripts) || self.matches_outpoints(outpoints)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Transaction {
+    /// Deserializes the transaction from an async reader
+    pub async fn deserialize_async<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self, ParseError> {
+        Transaction::deserialize_with_limits_async(reader, &Limits::consensus()).await
+    }
+
+    /// Deserializes the transaction from an async reader, checking given limits.
+    pub async fn deserialize_with_limits_async<R: AsyncRead + Unpin>(reader: &mut R, limits: &Limits) -> Result<Self, ParseError> {
+        let mut reader = reader.take(limits.max_transaction_size);
+        match Transaction::deserialize_unlimited_async(&mut reader, limits).await {
+            // The data ended because of the limit, not because of the reader.
+            Err(ref error) if error.is_unexpected_eof() && reader.limit() == 0 => {
+                Err(ParseError::SizeLimitExceeded { limit: limits.max_transaction_size })
+            },
+            result => result,
+        }
+    }
+
+    /// Same as `deserialize_with_limits` without limiting the number of bytes read.
+    async fn deserialize_unlimited_async<R: AsyncRead + Unpin>(reader: &mut R, limits: &Limits) -> Result<Self, ParseError> {
+        let version = reader.read_u32_le().await.map_err(ParseError::field("version"))?;
+        let mut input_count = deserialize_varint_async(reader).await.map_err(ParseError::field("input count"))?;
+
+        // See `deserialize_with_limits` for explanation.
+        let has_witness = input_count == 0;
+        if has_witness {
+            let flag = reader.read_u8().await.map_err(ParseError::field("SegWit flag"))?;
+            if flag != 1 {
+                return Err(ParseError::InvalidSegwitFlag { flag });
+            }
+            input_count = deserialize_varint_async(reader).await.map_err(ParseError::field("input count"))?;
+        }
+
+        if input_count > limits.max_inputs {
+            return Err(ParseError::TooManyInputs { count: input_count });
+        }
+        limits::checked_len(input_count)?;
+        let mut inputs = Vec::with_capacity(limits::preallocation(input_count));
+        for i in 0..input_count as usize {
+            inputs.push(TxInput::deserialize_with_limits_async(reader, limits).await.map_err(ParseError::item("input", i))?);
+        }
+
+        let output_count = deserialize_varint_async(reader).await.map_err(ParseError::field("output count"))?;
+        if output_count > limits.max_outputs {
+            return Err(ParseError::TooManyOutputs { count: output_count });
+        }
+        limits::checked_len(output_count)?;
+        let mut outputs = Vec::with_capacity(limits::preallocation(output_count));
+        for i in 0..output_count as usize {
+            outputs.push(TxOutput::deserialize_with_limits_async(reader, limits).await.map_err(ParseError::item("output", i))?);
+        }
+
+        let mut witnesses = Vec::new();
+        if has_witness {
+            witnesses.reserve_exact(inputs.len());
+            for i in 0..inputs.len() {
+                witnesses.push(Witness::deserialize_async(reader).await.map_err(ParseError::item("witness", i))?);
+            }
+        }
+        let lock_time = reader.read_u32_le().await.map_err(ParseError::field("lock time"))?;
+
+        Ok(Transaction {
+            version,
+            inputs,
+            outputs,
+            lock_time,
+            witnesses,
+        })
+    }
+}
+
+impl Transaction {
+    /// Returns the number of bytes `serialize` writes, without actually serializing.
+    pub fn serialized_len(&self) -> usize {
+        self.total_size()
+    }
+
+    /// Returns the size of the transaction serialized without witness.
+    pub fn base_size(&self) -> usize {
+        let inputs_len = self.inputs.iter().map(TxInput::serialized_len).sum::<usize>();
+        let outputs_len = self.outputs.iter().map(TxOutput::serialized_len).sum::<usize>();
+
+        4 + varint_len(self.inputs.len() as u64) + inputs_len
+            + varint_len(self.outputs.len() as u64) + outputs_len
+            + 4
+    }
+
+    /// Returns the size of the transaction serialized including witness.
+    pub fn total_size(&self) -> usize {
+        if self.witnesses.is_empty() {
+            return self.base_size();
+        }
+
+        // marker and flag
+        self.base_size() + 2 + self.witnesses.iter().map(Witness::serialized_len).sum::<usize>()
+    }
+
+    /// Computes the weight of the transaction as defined in BIP141.
+    pub fn weight(&self) -> usize {
+        self.base_size() * 3 + self.total_size()
+    }
+
+    /// Computes virtual size of the transaction, which is used for fee rates.
+    pub fn vsize(&self) -> usize {
+        self.weight().div_ceil(4)
+    }
+
+    /// Checks that the transaction has at least one input and one output.
+    ///
+    /// Deserialization doesn't check this so that malformed transactions can
+    /// still be inspected.
+    pub fn validate(&self) -> Result<(), ParseError> {
+        if self.inputs.is_empty() {
+            return Err(invalid(ParseError::NoInputs));
+        }
+        if self.outputs.is_empty() {
+            return Err(invalid(ParseError::NoOutputs));
+        }
+        Ok(())
+    }
+
+    /// Sums the values of all outputs.
+    ///
+    /// Returns `None` if the sum overflows, which can only happen for invalid transaction.
+    pub fn total_output(&self) -> Option<Amount> {
+        self.outputs.iter().try_fold(Amount::ZERO, |sum, output| sum.checked_add(output.satoshis))
+    }
+
+    /// Calculates the fee paid by the transaction.
+    ///
+    /// `prevouts[i]` must be the output spent by `inputs[i]`. Returns `None` if the lengths
+    /// don't match or the outputs are worth more than the inputs.
+    pub fn fee(&self, prevouts: &[TxOutput]) -> Option<Amount> {
+        if prevouts.len() != self.inputs.len() {
+            return None;
+        }
+
+        let total_input = prevouts.iter().try_fold(Amount::ZERO, |sum, prevout| sum.checked_add(prevout.satoshis))?;
+        total_input.checked_sub(self.total_output()?)
+    }
+
+    /// Returns the input at `index`, `None` if it's out of range.
+    pub fn input(&self, index: usize) -> Option<&TxInput> {
+        self.inputs.get(index)
+    }
+
+    /// Returns the output at `index`, `None` if it's out of range.
+    pub fn output(&self, index: usize) -> Option<&TxOutput> {
+        self.outputs.get(index)
+    }
+
+    /// Returns the witness of the input at `index`, `None` if it's out of range or the transaction has no witness.
+    pub fn witness(&self, index: usize) -> Option<&Witness> {
+        self.witnesses.get(index)
+    }
+
+    /// Replaces the script of the input.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of range.
+    pub fn set_input_script(&mut self, index: usize, script: Script) {
+        self.inputs[index].sig_script = script;
+    }
+
+    /// Replaces the sequence of the input.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of range.
+    pub fn set_sequence(&mut self, index: usize, sequence: u32) {
+        self.inputs[index].sequence = sequence;
+    }
+
+    /// Empties scripts of all inputs, keeping witnesses.
+    pub fn clear_all_input_scripts(&mut self) {
+        for input in &mut self.inputs {
+            input.sig_script.0.clear();
+        }
+    }
+
+    /// Appends the input, adding empty witness for it if the transaction has witnesses.
+    pub fn push_input(&mut self, input: TxInput) {
+        self.inputs.push(input);
+        if !self.witnesses.is_empty() {
+            self.witnesses.push(Witness(Vec::new()));
+        }
+    }
+
+    /// Appends the output.
+    pub fn push_output(&mut self, output: TxOutput) {
+        self.outputs.push(output);
+    }
+
+    /// Checks whether inputs and outputs are sorted as required by BIP69.
+    pub fn is_bip69_sorted(&self) -> bool {
+        self.inputs.windows(2).all(|pair| pair[0].outpoint <= pair[1].outpoint)
+            && self.outputs.windows(2).all(|pair| pair[0].bip69_cmp(&pair[1]) != Ordering::Greater)
+    }
+
+    /// Sorts inputs and outputs as required by BIP69, keeping witnesses with their inputs.
+    ///
+    /// This changes the signed data, so it should be done before signing.
+    /// Returns `false` without changing anything if there are witnesses but
+    /// their count differs from the count of inputs, because it's unknown which
+    /// witness belongs to which input then.
+    pub fn sort_bip69(&mut self) -> bool {
+        if self.witnesses.is_empty() {
+            self.inputs.sort_by(|a, b| a.outpoint.cmp(&b.outpoint));
+        } else if self.witnesses.len() == self.inputs.len() {
+            let mut pairs = self.inputs.drain(..).zip(self.witnesses.drain(..)).collect::<Vec<_>>();
+            pairs.sort_by(|(a, _), (b, _)| a.outpoint.cmp(&b.outpoint));
+            for (input, witness) in pairs {
+                self.inputs.push(input);
+                self.witnesses.push(witness);
+            }
+        } else {
+            return false;
+        }
+        self.outputs.sort_by(TxOutput::bip69_cmp);
+        true
+    }
+
+    /// Returns iterator over inputs paired with their witnesses.
+    pub fn input_infos(&self) -> impl Iterator<Item = InputInfo<'_>> {
+        self.inputs.iter().enumerate().map(move |(i, input)| InputInfo {
+            outpoint: &input.outpoint,
+            sequence: input.sequence,
+            witness: self.witnesses.get(i),
+        })
+    }
+
+    /// Returns iterator over outputs with classified scripts and their addresses.
+    pub fn output_infos(&self, network: Network) -> impl Iterator<Item = OutputInfo> + '_ {
+        self.outputs.iter().map(move |output| OutputInfo {
+            satoshis: output.satoshis,
+            script_type: output.verify_script.script_type(),
+            address: output.verify_script.address(network),
+        })
+    }
+
+    /// Returns interpreted lock time, the raw value is available in `lock_time` field.
+    pub fn lock_time(&self) -> LockTime {
+        LockTime::from_raw(self.lock_time)
+    }
+
+    /// Checks whether the transaction can be included in a block with given height and time.
+    ///
+    /// Same as `IsFinalTx` in Bitcoin Core: the lock time must be below the height or
+    /// time of the block, unless all inputs have the maximum sequence number.
+    pub fn is_final(&self, height: u32, block_time: u32) -> bool {
+        let locked = match self.lock_time() {
+            LockTime::None => false,
+            LockTime::Height(lock_height) => lock_height >= height,
+            LockTime::Time(lock_time) => lock_time >= block_time,
+        };
+        !locked || self.inputs.iter().all(|input| input.sequence == 0xFFFFFFFF)
+    }
+
+    /// Checks whether the transaction signals replaceability as defined in BIP125.
+    pub fn is_rbf(&self) -> bool {
+        self.inputs.iter().any(|input| input.sequence < 0xFFFFFFFE)
+    }
+
+    /// Checks whether the transaction is coinbase - the one creating new coins.
+    pub fn is_coinbase(&self) -> bool {
+        self.inputs.len() == 1 && self.inputs[0].outpoint.is_null()
+    }
+
+    /// Returns IDs of transactions whose outputs are spent by the inputs.
+    ///
+    /// The txid is repeated if multiple outputs of the same transaction are spent.
+    /// Coinbase spends nothing, so it returns empty vector.
+    pub fn prevout_txids(&self) -> Vec<&Hash256> {
+        if self.is_coinbase() {
+            return Vec::new();
+        }
+
+        self.inputs.iter().map(|input| &input.outpoint.txid).collect()
+    }
+
+    /// Returns the block height pushed at the beginning of coinbase script. (BIP34)
+    ///
+    /// Returns `None` if the transaction is not coinbase or the script doesn't
+    /// start with minimally encoded positive number. Coinbases before BIP34 may
+    /// start with arbitrary data, so the returned value is meaningful only for
+    /// blocks since version 2.
+    pub fn coinbase_height(&self) -> Option<u32> {
+        if !self.is_coinbase() {
+            return None;
+        }
+
+        let script = &self.inputs[0].sig_script;
+        match script.instructions().next()?.ok()? {
+            Instruction::Op(opcode) => {
+                // OP_1 ..= OP_16
+                match u8::from(opcode) {
+                    opcode @ 0x51..=0x60 => Some(u32::from(opcode - 0x50)),
+                    _ => None,
+                }
+            },
+            Instruction::PushBytes(bytes) => {
+                let (&last, rest) = bytes.split_last()?;
+                // Bigger heights don't fit into four bytes with the sign bit.
+                if bytes.len() > 4 || last & 0x80 != 0 {
+                    return None;
+                }
+                // Numbers must be pushed using the shortest opcode, which is OP_1 ..= OP_16
+                // for small ones.
+                if usize::from(script.0[0]) != bytes.len() || (rest.is_empty() && last <= 16) {
+                    return None;
+                }
+                // Zero byte at the end is only allowed if it's needed for the sign bit.
+                if last == 0 && rest.last().is_none_or(|&byte| byte & 0x80 == 0) {
+                    return None;
+                }
+
+                Some(bytes.iter().rev().fold(0, |height, &byte| height << 8 | u32::from(byte)))
+            },
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::hex::HexError;
+    use crate::test_vectors::*;
+    use crate::script::ScriptBuilder;
+
+    #[test]
+    fn legacy_round_trip() {
+        let data = hex::decode(TX_170).unwrap();
+        let transaction = Transaction::from_bytes(&data).unwrap();
+        assert!(transaction.witnesses.is_empty());
+        assert_eq!(transaction.inputs.len(), 1);
+        assert_eq!(transaction.outputs.len(), 2);
+
+        let mut serialized = Vec::new();
+        transaction.serialize(&mut serialized).unwrap();
+        assert_eq!(serialized, data);
+        assert_eq!(transaction.serialized_len(), data.len());
+    }
+
+    #[test]
+    fn segwit_round_trip() {
+        let data = hex::decode(BIP143_P2WPKH).unwrap();
+        let transaction = Transaction::from_bytes(&data).unwrap();
+        assert_eq!(transaction.witnesses.len(), 2);
+        assert!(transaction.witnesses[0].0.is_empty());
+        assert_eq!(transaction.witnesses[1].0.len(), 2);
+
+        assert_eq!(transaction.to_hex(), BIP143_P2WPKH);
+        assert_eq!(transaction.serialized_len(), data.len());
+    }
+
+    #[test]
+    fn legacy_ids() {
+        let transaction = Transaction::from_hex(TX_170).unwrap();
+        assert_eq!(transaction.txid().to_string(), TX_170_TXID);
+        assert_eq!(transaction.wtxid(), transaction.txid());
+    }
+
+    #[test]
+    fn segwit_ids_and_sizes() {
+        let transaction = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        assert_eq!(transaction.txid().to_string(), BIP143_P2WPKH_TXID);
+        assert_eq!(transaction.wtxid().to_string(), BIP143_P2WPKH_WTXID);
+        assert_eq!(transaction.base_size(), 233);
+        assert_eq!(transaction.total_size(), 343);
+        assert_eq!(transaction.weight(), 1042);
+        assert_eq!(transaction.vsize(), 261);
+    }
+
+    #[test]
+    fn legacy_sizes() {
+        let transaction = Transaction::from_hex(TX_170).unwrap();
+        assert_eq!(transaction.base_size(), 275);
+        assert_eq!(transaction.total_size(), 275);
+        assert_eq!(transaction.weight(), 1100);
+        assert_eq!(transaction.vsize(), 275);
+    }
+
+    #[test]
+    fn truncated_and_malformed() {
+        let data = hex::decode(TX_170).unwrap();
+        let error = Transaction::deserialize(&mut &data[..(data.len() - 1)]).unwrap_err();
+        assert!(matches!(error.root_cause(), ParseError::UnexpectedEof), "{:?}", error);
+
+        // script length of the first input replaced with 0xFFFFFFFF
+        let mut data = data;
+        data.splice(41..42, vec![0xfe, 0xff, 0xff, 0xff, 0xff]);
+        let error = Transaction::deserialize(&mut &*data).unwrap_err();
+        assert!(matches!(error.root_cause(), ParseError::ScriptTooLong { len: 0xFFFFFFFF }), "{:?}", error);
+    }
+
+    #[test]
+    fn from_hex() {
+        let transaction = Transaction::from_hex(&format!(" {}\n", TX_170)).unwrap();
+        assert_eq!(transaction.to_hex(), TX_170);
+
+        let error = Transaction::from_hex(&TX_170[1..]).err();
+        assert!(matches!(error, Some(ParseError::InvalidHex(HexError::OddLength))), "{:?}", error);
+        let error = Transaction::from_hex(&TX_170.replacen('0', "x", 1)).err();
+        assert!(matches!(error, Some(ParseError::InvalidHex(HexError::InvalidChar { index: 0 }))), "{:?}", error);
+    }
+
+    #[test]
+    fn coinbase() {
+        let coinbase = Transaction::from_hex(genesis_coinbase()).unwrap();
+        assert!(coinbase.is_coinbase());
+        assert!(coinbase.inputs[0].outpoint.is_null());
+
+        let spend = Transaction::from_hex(TX_170).unwrap();
+        assert!(!spend.is_coinbase());
+    }
+
+    #[test]
+    fn limits() {
+        let mut transaction = Transaction::from_hex(TX_170).unwrap();
+        transaction.outputs[0].verify_script.0 = vec![0x6a; 10_001];
+        let mut data = Vec::new();
+        transaction.serialize(&mut data).unwrap();
+
+        let error = Transaction::deserialize(&mut &*data).unwrap_err();
+        assert!(matches!(error.root_cause(), ParseError::ScriptTooLong { len: 10_001 }), "{:?}", error);
+        let parsed = Transaction::deserialize_with_limits(&mut &*data, &Limits::unlimited()).unwrap();
+        assert_eq!(parsed.outputs[0].verify_script.0.len(), 10_001);
+
+        let limits = Limits {
+            max_outputs: 1,
+            ..Limits::unlimited()
+        };
+        let error = Transaction::deserialize_with_limits(&mut &*data, &limits).unwrap_err();
+        assert!(matches!(error.root_cause(), ParseError::TooManyOutputs { count: 2 }), "{:?}", error);
+
+        let limits = Limits {
+            max_transaction_size: 100,
+            ..Limits::unlimited()
+        };
+        let error = Transaction::deserialize_with_limits(&mut &*data, &limits).unwrap_err();
+        assert!(matches!(error.root_cause(), ParseError::SizeLimitExceeded { limit: 100 }), "{:?}", error);
+    }
+
+    #[test]
+    fn total_output_overflow() {
+        let mut transaction = Transaction::from_hex(TX_170).unwrap();
+        assert_eq!(transaction.total_output(), Some(Amount(5_000_000_000)));
+
+        transaction.outputs[0].satoshis = Amount(u64::MAX - 1);
+        transaction.outputs[1].satoshis = Amount(u64::MAX - 1);
+        assert_eq!(transaction.total_output(), None);
+    }
+
+    #[test]
+    fn rbf() {
+        let mut transaction = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        transaction.inputs[0].sequence = 0xFFFFFFFF;
+        transaction.inputs[1].sequence = 0xFFFFFFFF;
+        assert!(!transaction.is_rbf());
+
+        transaction.inputs[1].sequence = 0xFFFFFFFE;
+        assert!(!transaction.is_rbf());
+
+        transaction.inputs[1].sequence = 0xFFFFFFFD;
+        assert!(transaction.is_rbf());
+
+        transaction.inputs[0].sequence = 0xFFFFFFFD;
+        assert!(transaction.is_rbf());
+    }
+
+    #[test]
+    fn fee() {
+        let transaction = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        let prevout = |satoshis| TxOutput {
+            satoshis: Amount(satoshis),
+            verify_script: Script(Vec::new()),
+        };
+        // values of the spent outputs are given in BIP143
+        let prevouts = [prevout(625_000_000), prevout(600_000_000)];
+
+        assert_eq!(transaction.total_output(), Some(Amount(335_790_000)));
+        assert_eq!(transaction.fee(&prevouts), Some(Amount(889_210_000)));
+        assert_eq!(transaction.fee(&prevouts[..1]), None);
+        assert_eq!(transaction.fee(&[prevout(1), prevout(1)]), None);
+    }
+
+    #[test]
+    fn validate() {
+        let transaction = Transaction::from_hex(TX_170).unwrap();
+        assert!(transaction.validate().is_ok());
+
+        let mut no_inputs = transaction.clone();
+        no_inputs.inputs.clear();
+        assert!(matches!(no_inputs.validate(), Err(ParseError::NoInputs)));
+
+        let mut no_outputs = transaction;
+        no_outputs.outputs.clear();
+        assert!(matches!(no_outputs.validate(), Err(ParseError::NoOutputs)));
+    }
+
+    #[test]
+    fn serialized_len_matches_serialize() {
+        fn len<F: FnOnce(&mut Vec<u8>) -> io::Result<()>>(serialize: F) -> usize {
+            let mut data = Vec::new();
+            serialize(&mut data).unwrap();
+            data.len()
+        }
+
+        for hex in &[TX_170, BIP143_P2WPKH] {
+            let transaction = Transaction::from_hex(hex).unwrap();
+            assert_eq!(transaction.serialized_len(), len(|data| transaction.serialize(data)));
+            assert_eq!(transaction.base_size(), len(|data| transaction.serialize_legacy(data)));
+            for input in &transaction.inputs {
+                assert_eq!(input.serialized_len(), len(|data| input.serialize(data)));
+            }
+            for output in &transaction.outputs {
+                assert_eq!(output.serialized_len(), len(|data| output.serialize(data)));
+            }
+            for witness in &transaction.witnesses {
+                assert_eq!(witness.serialized_len(), len(|data| witness.serialize(data)));
+            }
+        }
+    }
+
+    #[test]
+    fn peek_version_and_counts() {
+        // preceded by unrelated data, as in a larger stream
+        let mut data = vec![0xab; 3];
+        data.extend(hex::decode(BIP143_P2WPKH).unwrap());
+        let mut reader = io::Cursor::new(&data);
+        reader.set_position(3);
+
+        let header = Transaction::peek_version_and_counts(&mut reader).unwrap();
+        let expected = TransactionHeader {
+            version: 1,
+            has_witness: true,
+            input_count: 2,
+            output_count: 2,
+        };
+        assert_eq!(header, expected);
+        assert_eq!(reader.position(), 3);
+        assert_eq!(Transaction::deserialize(&mut reader).unwrap().txid().to_string(), BIP143_P2WPKH_TXID);
+
+        // rewinds on error too, the data ends in the sequence of the first input
+        let mut reader = io::Cursor::new(&data[..50]);
+        reader.set_position(3);
+        let error = Transaction::peek_version_and_counts(&mut reader).unwrap_err();
+        assert!(matches!(error, ParseError::Context { context: "input", index: Some(0), .. }), "{:?}", error);
+        assert!(matches!(error.root_cause(), ParseError::UnexpectedEof), "{:?}", error);
+        assert_eq!(reader.position(), 3);
+    }
+
+    #[test]
+    fn skip_matches_deserialize() {
+        let data = hex::decode(BIP143_P2WPKH).unwrap();
+        let limits = Limits::consensus();
+        // inputs start after version, marker, flag and input count
+        let mut skipped = &data[7..];
+        let mut parsed = &data[7..];
+        for _ in 0..2 {
+            TxInput::skip(&mut skipped, &limits).unwrap();
+            TxInput::deserialize(&mut parsed).unwrap();
+            assert_eq!(skipped.len(), parsed.len());
+        }
+
+        // output count
+        skipped = &skipped[1..];
+        parsed = &parsed[1..];
+        for _ in 0..2 {
+            TxOutput::skip(&mut skipped, &limits).unwrap();
+            TxOutput::deserialize(&mut parsed).unwrap();
+            assert_eq!(skipped.len(), parsed.len());
+        }
+
+        let error = TxInput::skip(&mut &data[7..20], &limits).unwrap_err();
+        assert!(matches!(error, ParseError::Context { context: "outpoint", .. }), "{:?}", error);
+        let error = TxOutput::skip(&mut &[0; 7][..], &limits).unwrap_err();
+        assert!(matches!(error, ParseError::Context { context: "value", .. }), "{:?}", error);
+    }
+
+    #[test]
+    fn streaming_matches_full_parse() {
+        #[derive(Debug, PartialEq)]
+        enum Item {
+            Input(TxInput),
+            Output(TxOutput),
+            Witness(Witness),
+        }
+
+        let data = hex::decode(BIP143_P2WPKH).unwrap();
+        let items = std::cell::RefCell::new(Vec::new());
+        let mut reader = &data[..];
+        let streamed = Transaction::deserialize_streaming(
+            &mut reader,
+            |input| items.borrow_mut().push(Item::Input(input)),
+            |output| items.borrow_mut().push(Item::Output(output)),
+            |witness| items.borrow_mut().push(Item::Witness(witness)),
+        ).unwrap();
+        assert!(reader.is_empty());
+
+        let transaction = Transaction::from_bytes(&data).unwrap();
+        let expected = transaction.inputs.iter().cloned().map(Item::Input)
+            .chain(transaction.outputs.iter().cloned().map(Item::Output))
+            .chain(transaction.witnesses.iter().cloned().map(Item::Witness))
+            .collect::<Vec<_>>();
+        assert_eq!(expected.len(), 6);
+        assert_eq!(items.into_inner(), expected);
+
+        let header = TransactionHeader {
+            version: transaction.version,
+            has_witness: true,
+            input_count: 2,
+            output_count: 2,
+        };
+        assert_eq!(streamed, StreamedTransaction { header, lock_time: transaction.lock_time });
+    }
+
+    #[test]
+    fn zero_input_count_is_segwit_marker() {
+        let data = hex::decode(BIP143_P2WPKH).unwrap();
+        assert_eq!(data[4..6], [0x00, 0x01]);
+        let streamed = Transaction::deserialize_streaming(&mut &*data, |_| (), |_| (), |_| ()).unwrap();
+        assert!(streamed.header.has_witness);
+        assert_eq!(streamed.header.input_count, 2);
+
+        let mut invalid_flag = data.clone();
+        invalid_flag[5] = 0x00;
+        assert!(matches!(Transaction::deserialize(&mut &*invalid_flag), Err(ParseError::InvalidSegwitFlag { flag: 0 })));
+    }
+
+    #[test]
+    fn legacy_without_inputs() {
+        let mut transaction = Transaction::from_hex(TX_170).unwrap();
+        transaction.inputs.clear();
+        let mut data = Vec::new();
+        transaction.serialize_legacy(&mut data).unwrap();
+
+        // output count 2 is read as the flag
+        assert!(matches!(Transaction::deserialize(&mut &*data), Err(ParseError::InvalidSegwitFlag { flag: 2 })));
+
+        // output count 1 is a valid flag, the output is then misparsed as inputs
+        transaction.outputs.truncate(1);
+        data.clear();
+        transaction.serialize_legacy(&mut data).unwrap();
+        assert!(Transaction::deserialize(&mut &*data).is_err());
+    }
+
+    #[test]
+    fn duplicate_inputs() {
+        let mut transaction = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        assert!(!transaction.has_duplicate_inputs());
+
+        transaction.inputs[1].outpoint = transaction.inputs[0].outpoint.clone();
+        assert!(transaction.has_duplicate_inputs());
+
+        transaction.inputs[1].outpoint.index += 1;
+        assert!(!transaction.has_duplicate_inputs());
+    }
+
+    #[test]
+    fn field_endianness() {
+        let data = hex::decode(TX_170).unwrap();
+        let mut transaction = Transaction::from_bytes(&data).unwrap();
+
+        // hashes are stored in wire order and displayed reversed
+        let spent_txid = &transaction.inputs[0].outpoint.txid;
+        assert_eq!(spent_txid.0[..], data[5..37]);
+        assert_eq!(spent_txid.to_string(), "0437cd7f8525ceed2324359c2d0ba26006d92d856a9c20fa0241106ee5a597c9");
+        assert_eq!(transaction.txid().0[0], 0x16);
+        assert_eq!(transaction.txid().to_string(), TX_170_TXID);
+
+        transaction.version = 0x01020304;
+        transaction.inputs[0].outpoint.index = 0x05060708;
+        transaction.inputs[0].sequence = 0x090a0b0c;
+        transaction.outputs[0].satoshis = Amount(0x1112131415161718);
+        transaction.lock_time = 0x1d1e1f20;
+        let mut serialized = Vec::new();
+        transaction.serialize(&mut serialized).unwrap();
+
+        // version, input count, outpoint, script length, script, sequence, output count, value
+        assert_eq!(serialized[..4], [0x04, 0x03, 0x02, 0x01]);
+        assert_eq!(serialized[37..41], [0x08, 0x07, 0x06, 0x05]);
+        assert_eq!(serialized[114..118], [0x0c, 0x0b, 0x0a, 0x09]);
+        assert_eq!(serialized[119..127], [0x18, 0x17, 0x16, 0x15, 0x14, 0x13, 0x12, 0x11]);
+        assert_eq!(serialized[serialized.len() - 4..], [0x20, 0x1f, 0x1e, 0x1d]);
+        assert_eq!(Transaction::from_bytes(&serialized).unwrap(), transaction);
+    }
+
+    #[test]
+    fn coinbase_height() {
+        let mut coinbase = Transaction::from_hex(TESTNET_COINBASE_100000).unwrap();
+        assert_eq!(coinbase.txid().to_string(), TESTNET_COINBASE_100000_TXID);
+        assert_eq!(coinbase.coinbase_height(), Some(100_000));
+
+        // nine-byte push
+        let mut script = hex::decode("09a08601112233445566").unwrap();
+        script.extend_from_slice(&coinbase.inputs[0].sig_script.0[4..]);
+        coinbase.inputs[0].sig_script = Script(script);
+        assert_eq!(coinbase.coinbase_height(), None);
+
+        coinbase.inputs[0].sig_script = Script(vec![0x5a]);
+        assert_eq!(coinbase.coinbase_height(), Some(10));
+
+        // non-minimal push of 10
+        coinbase.inputs[0].sig_script = Script(vec![0x01, 0x0a]);
+        assert_eq!(coinbase.coinbase_height(), None);
+
+        let spend = Transaction::from_hex(TX_170).unwrap();
+        assert_eq!(spend.coinbase_height(), None);
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let hex = format!("{}abcd", TX_170);
+        assert!(matches!(Transaction::from_hex(&hex), Err(ParseError::TrailingBytes(2))));
+
+        let data = hex::decode(&hex).unwrap();
+        assert!(matches!(Transaction::from_bytes(&data), Err(ParseError::TrailingBytes(2))));
+
+        let mut reader = &*data;
+        let transaction = Transaction::deserialize(&mut reader).unwrap();
+        assert_eq!(transaction.txid().to_string(), TX_170_TXID);
+        assert_eq!(reader, [0xab, 0xcd]);
+    }
+
+    #[test]
+    fn dust() {
+        let output = |script: &str, satoshis| TxOutput {
+            satoshis: Amount(satoshis),
+            verify_script: Script(hex::decode(script).unwrap()),
+        };
+        let p2pkh = "76a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac";
+        let p2wpkh = "00148280b37df378db99f66f85c95a783a76ac7a6d59";
+
+        assert_eq!(output(p2pkh, 0).dust_threshold(3000), Amount(546));
+        assert!(output(p2pkh, 545).is_dust(3000));
+        assert!(!output(p2pkh, 546).is_dust(3000));
+
+        assert_eq!(output(p2wpkh, 0).dust_threshold(3000), Amount(294));
+        assert!(output(p2wpkh, 293).is_dust(3000));
+        assert!(!output(p2wpkh, 294).is_dust(3000));
+
+        assert_eq!(output("6a0100", 0).dust_threshold(3000), Amount::ZERO);
+
+        assert_eq!(output(p2pkh, 0).dust_threshold(u64::MAX), Amount(u64::MAX / 1000));
+        assert!(output(p2pkh, 21_000_000 * 100_000_000).is_dust(u64::MAX));
+    }
+
+    #[test]
+    fn matches_filter() {
+        let transaction = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        let present = transaction.outputs[1].verify_script.0.clone();
+        let absent = hex::decode("76a914000000000000000000000000000000000000000088ac").unwrap();
+
+        let mut scripts = HashSet::new();
+        scripts.insert(absent);
+        let mut outpoints = HashSet::new();
+        assert!(!transaction.matches_scripts(&scripts));
+        assert!(!transaction.matches_filter(&scripts, &outpoints));
+
+        scripts.insert(present);
+        assert!(transaction.matches_scripts(&scripts));
+        assert!(transaction.matches_filter(&scripts, &outpoints));
+
+        scripts.clear();
+        outpoints.insert(transaction.inputs[1].outpoint.clone());
+        assert!(transaction.matches_filter(&scripts, &outpoints));
+    }
+
+    #[test]
+    fn matches_outpoints() {
+        let transaction = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        let scripts = transaction.outputs.iter().map(|output| output.verify_script.0.clone()).collect::<HashSet<_>>();
+        let spent = transaction.inputs[0].outpoint.clone();
+        let unspent = Outpoint {
+            txid: spent.txid.clone(),
+            index: spent.index + 1,
+        };
+
+        let mut outpoints = HashSet::new();
+        outpoints.insert(unspent);
+        assert!(!transaction.matches_outpoints(&outpoints));
+        assert!(!transaction.matches_filter(&HashSet::new(), &outpoints));
+        assert!(transaction.matches_filter(&scripts, &outpoints));
+
+        outpoints.insert(spent);
+        assert!(transaction.matches_outpoints(&outpoints));
+        assert!(transaction.matches_filter(&HashSet::new(), &outpoints));
+    }
+
+    #[test]
+    fn mutation() {
+        let mut transaction = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        transaction.set_input_script(1, Script(vec![0x51]));
+        transaction.set_sequence(0, 0xFFFFFFFD);
+        assert_eq!(transaction.inputs[1].sig_script, Script(vec![0x51]));
+        assert!(transaction.is_rbf());
+
+        transaction.clear_all_input_scripts();
+        assert!(transaction.inputs.iter().all(|input| input.sig_script.0.is_empty()));
+
+        let input = transaction.inputs[0].clone();
+        transaction.push_input(input.clone());
+        assert_eq!(transaction.inputs.len(), 3);
+        assert_eq!(transaction.witnesses.len(), 3);
+        assert!(transaction.witnesses[2].0.is_empty());
+
+        let output = transaction.outputs[0].clone();
+        transaction.push_output(output);
+        assert_eq!(transaction.outputs.len(), 3);
+        assert_eq!(Transaction::from_hex(&transaction.to_hex()).unwrap(), transaction);
+
+        let mut legacy = Transaction::from_hex(TX_170).unwrap();
+        legacy.push_input(input);
+        assert!(legacy.witnesses.is_empty());
+        assert_eq!(Transaction::from_hex(&legacy.to_hex()).unwrap(), legacy);
+    }
+
+    #[test]
+    fn prevout_txids() {
+        let block = crate::block::Block::deserialize(&mut &*hex::decode(BLOCK_TWO_TXS).unwrap()).unwrap();
+        let coinbase = &block.transactions[0];
+        assert!(coinbase.prevout_txids().is_empty());
+
+        let spend = &block.transactions[1];
+        let txids = spend.prevout_txids();
+        assert_eq!(txids.len(), 3);
+        assert_eq!(txids[0].to_string(), "264299886446921c89e598ec2b1ec3eab6a2c9b0235b310ff513a039315ff721");
+        assert_eq!(txids, spend.inputs.iter().map(|input| &input.outpoint.txid).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn from_lenient_hex() {
+        let transaction = Transaction::from_hex(TX_170).unwrap();
+        let spaced = TX_170.as_bytes().chunks(2).map(|byte| std::str::from_utf8(byte).unwrap()).collect::<Vec<_>>().join(" ");
+
+        for hex in &[format!("0x{}", TX_170), spaced, format!("{}\n", TX_170), format!("0X{}", TX_170.to_uppercase())] {
+            assert_eq!(Transaction::from_hex(hex).unwrap(), transaction);
+        }
+        assert!(matches!(Transaction::from_hex(&format!("0x{}0", TX_170)), Err(ParseError::InvalidHex(hex::HexError::OddLength))));
+    }
+
+    #[test]
+    fn sanity_check() {
+        let transaction = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        assert!(transaction.sanity_check().is_ok());
+
+        let mut no_inputs = transaction.clone();
+        no_inputs.inputs.clear();
+        no_inputs.witnesses.clear();
+        assert!(matches!(no_inputs.sanity_check(), Err(ParseError::NoInputs)));
+
+        let mut no_outputs = transaction.clone();
+        no_outputs.outputs.clear();
+        assert!(matches!(no_outputs.sanity_check(), Err(ParseError::NoOutputs)));
+
+        let mut too_much = transaction.clone();
+        too_much.outputs[0].satoshis = Amount(Amount::MAX_MONEY.0 + 1);
+        assert!(matches!(too_much.sanity_check(), Err(ParseError::InvalidAmount(2_100_000_000_000_001))));
+
+        let mut sum_too_large = transaction.clone();
+        sum_too_large.outputs[0].satoshis = Amount::MAX_MONEY;
+        sum_too_large.outputs[1].satoshis = Amount(1);
+        assert!(matches!(sum_too_large.sanity_check(), Err(ParseError::TotalOutputTooLarge)));
+
+        let mut duplicate = transaction.clone();
+        duplicate.inputs[1].outpoint = duplicate.inputs[0].outpoint.clone();
+        assert!(matches!(duplicate.sanity_check(), Err(ParseError::DuplicateInputs)));
+
+        let mut too_large = transaction;
+        too_large.outputs[0].verify_script = Script(vec![0x51; 1_000_000]);
+        match too_large.sanity_check() {
+            Err(ParseError::TransactionTooLarge { weight }) => assert!(weight > 4_000_000),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn bip69() {
+        let original = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        // the second txid is lower when displayed
+        assert!(!original.is_bip69_sorted());
+
+        let mut transaction = original.clone();
+        assert!(transaction.sort_bip69());
+        assert!(transaction.is_bip69_sorted());
+        assert_eq!(transaction.inputs, [original.inputs[1].clone(), original.inputs[0].clone()]);
+        assert_eq!(transaction.witnesses, [original.witnesses[1].clone(), original.witnesses[0].clone()]);
+        assert_eq!(transaction.outputs, original.outputs);
+
+        let mut outputs_unsorted = transaction.clone();
+        outputs_unsorted.outputs.swap(0, 1);
+        assert!(!outputs_unsorted.is_bip69_sorted());
+        assert!(outputs_unsorted.sort_bip69());
+        assert_eq!(outputs_unsorted, transaction);
+
+        let mut legacy = original.clone();
+        legacy.witnesses.clear();
+        assert!(legacy.sort_bip69());
+        assert!(legacy.is_bip69_sorted());
+
+        let mut mismatched = original.clone();
+        mismatched.witnesses.truncate(1);
+        let before = mismatched.clone();
+        assert!(!mismatched.sort_bip69());
+        assert_eq!(mismatched, before);
+    }
+
+    #[test]
+    fn deserialize_exact() {
+        let mut data = hex::decode(TX_170).unwrap();
+        let len = data.len();
+        data.extend_from_slice(&[0xab, 0xcd]);
+
+        let mut reader = &*data;
+        let transaction = Transaction::deserialize_exact(&mut reader, len).unwrap();
+        assert_eq!(transaction.txid().to_string(), TX_170_TXID);
+        assert_eq!(reader, [0xab, 0xcd]);
+
+        // the frame is longer, its rest is skipped
+        let mut reader = &*data;
+        match Transaction::deserialize_exact(&mut reader, len + 1) {
+            Err(ParseError::LengthMismatch { expected, actual }) => assert_eq!((expected, actual), (len + 1, len)),
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert_eq!(reader, [0xcd]);
+
+        // the frame is shorter, nothing after it is read
+        let mut reader = &*data;
+        match Transaction::deserialize_exact(&mut reader, len - 1) {
+            Err(ParseError::LengthMismatch { expected, actual }) => assert_eq!((expected, actual), (len - 1, len - 1)),
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert_eq!(reader, &data[len - 1..]);
+
+        // the data ends before the frame
+        let result = Transaction::deserialize_exact(&mut &data[..len - 1], len);
+        assert!(matches!(result.unwrap_err().root_cause(), ParseError::UnexpectedEof));
+    }
+
+    #[test]
+    fn infer_spent_script_type() {
+        let transaction = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        let spends_p2pk = &transaction.inputs[0];
+        assert_eq!(spends_p2pk.infer_spent_script_type(transaction.witness(0)), Some(ScriptType::P2pk));
+        let spends_p2wpkh = &transaction.inputs[1];
+        assert_eq!(spends_p2wpkh.infer_spent_script_type(transaction.witness(1)), Some(ScriptType::P2wpkh));
+
+        let signature = match spends_p2pk.sig_script.instructions().next() {
+            Some(Ok(Instruction::PushBytes(signature))) => signature,
+            instruction => panic!("unexpected instruction {:?}", instruction),
+        };
+        let public_key = &transaction.witnesses[1].0[1];
+        let mut spends_p2pkh = spends_p2pk.clone();
+        spends_p2pkh.sig_script = ScriptBuilder::new().push_slice(signature).push_slice(public_key).into_script();
+        assert_eq!(spends_p2pkh.infer_spent_script_type(None), Some(ScriptType::P2pkh));
+
+        // OP_0 <signature> <1-of-1 multisig redeem script>
+        let mut redeem_script = vec![0x51, 0x21];
+        redeem_script.extend_from_slice(public_key);
+        redeem_script.extend_from_slice(&[0x51, 0xae]);
+        let mut spends_p2sh = spends_p2pk.clone();
+        spends_p2sh.sig_script = ScriptBuilder::new().push_slice(&[]).push_slice(signature).push_slice(&redeem_script).into_script();
+        assert_eq!(spends_p2sh.infer_spent_script_type(None), Some(ScriptType::P2sh));
+
+        let mut empty = spends_p2pk.clone();
+        empty.sig_script = Script(Vec::new());
+        assert_eq!(empty.infer_spent_script_type(None), None);
+    }
+
+    #[test]
+    fn strict_output_value() {
+        let output = |satoshis| {
+            let mut data = Vec::new();
+            TxOutput { satoshis: Amount(satoshis), verify_script: Script(vec![0x51]) }.serialize(&mut data).unwrap();
+            data
+        };
+
+        let too_much = output(Amount::MAX_MONEY.0 + 1);
+        match TxOutput::deserialize_with_limits(&mut &*too_much, &Limits::strict()) {
+            Err(ParseError::InvalidAmount(satoshis)) => assert_eq!(satoshis, Amount::MAX_MONEY.0 + 1),
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert_eq!(TxOutput::deserialize(&mut &*too_much).unwrap().satoshis, Amount(Amount::MAX_MONEY.0 + 1));
+
+        let max = output(Amount::MAX_MONEY.0);
+        assert!(TxOutput::deserialize_with_limits(&mut &*max, &Limits::strict()).is_ok());
+
+        let mut transaction = Transaction::from_hex(TX_170).unwrap();
+        transaction.outputs[0].satoshis = Amount(Amount::MAX_MONEY.0 + 1);
+        let data = hex::decode(&transaction.to_hex()).unwrap();
+        assert!(Transaction::deserialize_with_limits(&mut &*data, &Limits::strict()).is_err());
+        assert!(Transaction::deserialize(&mut &*data).is_ok());
+    }
+
+    #[test]
+    fn input_infos() {
+        let transaction = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        let infos = transaction.input_infos().collect::<Vec<_>>();
+        assert_eq!(infos.len(), 2);
+        assert_eq!(infos[0].outpoint, &transaction.inputs[0].outpoint);
+        assert_eq!(infos[0].sequence, 0xFFFFFFEE);
+        assert_eq!(infos[0].witness.unwrap().0.len(), 0);
+        assert_eq!(infos[1].outpoint.index, 1);
+        assert_eq!(infos[1].sequence, 0xFFFFFFFF);
+        assert_eq!(infos[1].witness, Some(&transaction.witnesses[1]));
+
+        let legacy = Transaction::from_hex(TX_170).unwrap();
+        assert!(legacy.input_infos().all(|info| info.witness.is_none()));
+    }
+
+    #[test]
+    fn output_infos() {
+        let transaction = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        let infos = transaction.output_infos(Network::Mainnet).collect::<Vec<_>>();
+        let expected = [
+            OutputInfo {
+                satoshis: Amount(112_340_000),
+                script_type: ScriptType::P2pkh,
+                address: Some("1Cu32FVupVCgHkMMRJdYJugxwo2Aprgk7H".to_owned()),
+            },
+            OutputInfo {
+                satoshis: Amount(223_450_000),
+                script_type: ScriptType::P2pkh,
+                address: Some("16TZ8J6Q5iZKBWizWzFAYnrsaox5Z5aBRV".to_owned()),
+            },
+        ];
+        assert_eq!(infos, expected);
+        let testnet = transaction.output_infos(Network::Testnet).next().unwrap();
+        assert_eq!(testnet.address.unwrap(), "msQzKJatdWdw4rpy8sbv8puHoncseekYCf");
+
+        // P2PK has no address
+        let legacy = Transaction::from_hex(TX_170).unwrap();
+        for info in legacy.output_infos(Network::Mainnet) {
+            assert_eq!(info.script_type, ScriptType::P2pk);
+            assert_eq!(info.address, None);
+        }
+    }
+
+    #[test]
+    fn lock_time_from_raw() {
+        assert_eq!(LockTime::from_raw(0), LockTime::None);
+        assert_eq!(LockTime::from_raw(1), LockTime::Height(1));
+        assert_eq!(LockTime::from_raw(499_999_999), LockTime::Height(499_999_999));
+        assert_eq!(LockTime::from_raw(LockTime::THRESHOLD), LockTime::Time(500_000_000));
+        assert_eq!(LockTime::from_raw(u32::MAX), LockTime::Time(u32::MAX));
+    }
+
+    #[test]
+    fn is_final() {
+        // lock time 17, first input has sequence 0xFFFFFFEE
+        let mut transaction = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        assert_eq!(transaction.lock_time(), LockTime::Height(17));
+        assert!(!transaction.is_final(16, 0));
+        assert!(!transaction.is_final(17, 0));
+        assert!(transaction.is_final(18, 0));
+
+        // the last height, block time doesn't matter
+        transaction.lock_time = 499_999_999;
+        assert!(!transaction.is_final(499_999_999, u32::MAX));
+        assert!(transaction.is_final(500_000_000, 0));
+
+        // the first time, height doesn't matter
+        transaction.lock_time = 500_000_000;
+        assert!(!transaction.is_final(u32::MAX, 500_000_000));
+        assert!(transaction.is_final(0, 500_000_001));
+
+        transaction.inputs[0].sequence = 0xFFFFFFFF;
+        assert!(transaction.is_final(0, 0));
+
+        transaction.inputs[0].sequence = 0xFFFFFFFE;
+        transaction.lock_time = 0;
+        assert!(transaction.is_final(0, 0));
+        transaction.lock_time = 1;
+        assert!(!transaction.is_final(1, u32::MAX));
+        assert!(transaction.is_final(2, 0));
+    }
+
+    #[test]
+    fn builder() {
+        let previous = Transaction::from_hex(TX_170).unwrap();
+        let outpoint = Outpoint {
+            txid: previous.txid(),
+            index: 1,
+        };
+        let sig_script = ScriptBuilder::new().push_slice(&[0x30; 71]).into_script();
+        let transaction = TransactionBuilder::new(2)
+            .add_input(outpoint.clone(), sig_script.clone(), 0xFFFFFFFD)
+            .add_output(Amount(3_999_990_000), previous.outputs[1].verify_script.clone())
+            .lock_time(170)
+            .build();
+        assert_eq!(transaction.version, 2);
+        assert_eq!(transaction.inputs[0].outpoint, outpoint);
+        assert_eq!(transaction.inputs[0].sig_script, sig_script);
+        assert!(transaction.witnesses.is_empty());
+
+        let mut data = Vec::new();
+        transaction.serialize(&mut data).unwrap();
+        // version, input count, input, output count, output and lock time
+        assert_eq!(data.len(), 4 + 1 + (36 + 1 + 72 + 4) + 1 + (8 + 1 + 67) + 4);
+        assert_eq!(data[..5], [0x02, 0, 0, 0, 0x01]);
+        assert_eq!(data[data.len() - 4..], [0xaa, 0, 0, 0]);
+        assert_eq!(Transaction::from_bytes(&data).unwrap(), transaction);
+    }
+
+    #[test]
+    fn all_witnesses_empty() {
+        let mut transaction = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        transaction.witnesses[1].0.clear();
+
+        let mut data = Vec::new();
+        transaction.serialize(&mut data).unwrap();
+        assert_eq!(data[4..6], [0x00, 0x01]);
+        // two empty stacks followed by lock time
+        assert_eq!(data[data.len() - 6..], [0x00, 0x00, 0x11, 0, 0, 0]);
+        assert_eq!(data.len(), transaction.base_size() + 4);
+
+        let parsed = Transaction::from_bytes(&data).unwrap();
+        assert_eq!(parsed.witnesses.len(), 2);
+        assert!(parsed.witnesses.iter().all(|witness| witness.0.is_empty()));
+        assert_eq!(parsed, transaction);
+        assert_eq!(parsed.txid().to_string(), BIP143_P2WPKH_TXID);
+        assert_ne!(parsed.wtxid(), parsed.txid());
+    }
+
+    #[test]
+    fn deserialize_stripped() {
+        let mut data = hex::decode(BIP143_P2WPKH).unwrap();
+        let len = data.len();
+        data.extend(hex::decode(TX_170).unwrap());
+
+        let mut reader = &data[..];
+        let full = Transaction::deserialize(&mut reader).unwrap();
+        assert_eq!(data.len() - reader.len(), len);
+
+        let mut reader = &data[..];
+        let stripped = Transaction::deserialize_stripped_with_limits(&mut reader, &Limits::consensus()).unwrap();
+        assert_eq!(data.len() - reader.len(), len);
+        assert!(stripped.witnesses.is_empty());
+        assert_eq!(stripped, Transaction { witnesses: Vec::new(), ..full.clone() });
+        assert_eq!(stripped.txid(), full.txid());
+        // the next transaction follows right after the witnesses
+        assert_eq!(Transaction::deserialize(&mut reader).unwrap().txid().to_string(), TX_170_TXID);
+
+        let limits = Limits {
+            max_outputs: 1,
+            ..Limits::consensus()
+        };
+        let error = Transaction::deserialize_stripped_with_limits(&mut &data[..], &limits).unwrap_err();
+        assert!(matches!(error.root_cause(), ParseError::TooManyOutputs { count: 2 }), "{:?}", error);
+    }
+
+    #[test]
+    fn checked_accessors() {
+        let transaction = Transaction::from_hex(TX_170).unwrap();
+        assert_eq!(transaction.input(0), Some(&transaction.inputs[0]));
+        assert_eq!(transaction.input(1), None);
+        assert_eq!(transaction.output(1), Some(&transaction.outputs[1]));
+        assert_eq!(transaction.output(2), None);
+        assert_eq!(transaction.output(usize::MAX), None);
+        // no witness at all
+        assert_eq!(transaction.witness(0), None);
+
+        let segwit = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        assert_eq!(segwit.witness(1), Some(&segwit.witnesses[1]));
+        assert_eq!(segwit.witness(2), None);
+    }
+
+    #[test]
+    fn wtxid() {
+        let segwit = Transaction::from_hex(BIP143_P2WPKH).unwrap();
+        assert_ne!(segwit.wtxid(), segwit.txid());
+
+        let mut legacy = segwit.clone();
+        legacy.witnesses.clear();
+        assert_eq!(legacy.wtxid(), legacy.txid());
+        assert_eq!(legacy.txid(), segwit.txid());
+
+        let coinbase = Transaction::from_hex(SEGWIT_COINBASE).unwrap();
+        assert!(!coinbase.witnesses.is_empty());
+        assert_eq!(coinbase.wtxid(), Hash256([0; 32]));
+        assert_ne!(coinbase.txid(), Hash256([0; 32]));
+    }
+
+    #[test]
+    fn deserialize_counted() {
+        let mut data = hex::decode(TX_170).unwrap();
+        let len = data.len();
+        data.push(0xff);
+        let (transaction, count) = Transaction::deserialize_counted(&mut &data[..]).unwrap();
+        assert_eq!(count, len);
+        assert_eq!(transaction.txid().to_string(), TX_170_TXID);
+
+        let limits = Limits {
+            max_outputs: 1,
+            ..Limits::consensus()
+        };
+        let error = Transaction::deserialize_counted_with_limits(&mut &data[..], &limits).unwrap_err();
+        assert!(matches!(error.root_cause(), ParseError::TooManyOutputs { count: 2 }), "{:?}", error);
+    }
+}
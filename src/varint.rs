@@ -0,0 +1,219 @@
+use byteorder::{ByteOrder, LE};
+#[cfg(feature = "std")]
+use byteorder::{ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::{Read, Write};
+#[cfg(feature = "std")]
+use crate::error::ParseError;
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// "Varint" as defined by Bitcoin protocol, also called "CompactSize".
+///
+/// Values are always serialized using the shortest encoding (1, 3, 5 or 9 bytes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct VarInt(pub u64);
+
+#[cfg(feature = "std")]
+impl VarInt {
+    /// Deserializes the varint, accepting non-shortest encodings too.
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let first = reader.read_u8()?;
+        let mut rest = [0; 8];
+        let rest = &mut rest[..VarInt::remaining_len(first)];
+        reader.read_exact(rest)?;
+        Ok(VarInt::decode(first, rest))
+    }
+
+    /// Deserializes the varint, rejecting encodings which aren't the shortest possible.
+    ///
+    /// Bitcoin always serializes varints this way, so other encodings indicate
+    /// corrupted or maliciously crafted data.
+    pub fn deserialize_canonical<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let first = reader.read_u8()?;
+        let mut rest = [0; 8];
+        let rest = &mut rest[..VarInt::remaining_len(first)];
+        reader.read_exact(rest)?;
+
+        let varint = VarInt::decode(first, rest);
+        if varint.serialized_len() != 1 + rest.len() {
+            return Err(ParseError::NonCanonicalVarint { value: varint.0 });
+        }
+        Ok(varint)
+    }
+
+    /// Serializes the varint using the shortest encoding.
+    ///
+    /// This is exact inverse of `deserialize`.
+    pub fn serialize<W: Write>(self, writer: &mut W) -> io::Result<()> {
+        let value = self.0;
+        match value {
+            0..=252 => writer.write_u8(value as u8),
+            253..=0xFFFF => {
+                writer.write_u8(253)?;
+                writer.write_u16::<LE>(value as u16)
+            },
+            0x10000..=0xFFFF_FFFF => {
+                writer.write_u8(254)?;
+                writer.write_u32::<LE>(value as u32)
+            },
+            _ => {
+                writer.write_u8(255)?;
+                writer.write_u64::<LE>(value)
+            },
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl VarInt {
+    /// Deserializes the varint from an async reader.
+    pub async fn deserialize_async<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self, ParseError> {
+        let first = reader.read_u8().await?;
+        let mut rest = [0; 8];
+        let rest = &mut rest[..VarInt::remaining_len(first)];
+        reader.read_exact(rest).await?;
+        Ok(VarInt::decode(first, rest))
+    }
+}
+
+impl VarInt {
+    /// Returns how many bytes follow the first byte of the encoding.
+    pub(crate) fn remaining_len(first: u8) -> usize {
+        match first {
+            253 => 2,
+            254 => 4,
+            255 => 8,
+            _   => 0,
+        }
+    }
+
+    /// Decodes the varint from its first byte and `remaining_len(first)` bytes following it.
+    ///
+    /// This is shared by all readers, non-shortest encodings are accepted.
+    pub(crate) fn decode(first: u8, rest: &[u8]) -> Self {
+        let value = match first {
+            253 => LE::read_u16(rest).into(),
+            254 => LE::read_u32(rest).into(),
+            255 => LE::read_u64(rest),
+            x   => x.into(),
+        };
+
+        VarInt(value)
+    }
+
+    /// Returns the number of bytes `serialize` writes.
+    pub fn serialized_len(self) -> usize {
+        match self.0 {
+            0..=252 => 1,
+            253..=0xFFFF => 3,
+            0x10000..=0xFFFF_FFFF => 5,
+            _ => 9,
+        }
+    }
+}
+
+impl From<u64> for VarInt {
+    fn from(value: u64) -> Self {
+        VarInt(value)
+    }
+}
+
+impl From<VarInt> for u64 {
+    fn from(value: VarInt) -> Self {
+        value.0
+    }
+}
+
+/// Deserializes "varint" as defined by Bitcoin protocol.
+#[cfg(feature = "std")]
+pub fn deserialize_varint<R: Read>(reader: &mut R) -> Result<u64, ParseError> {
+    VarInt::deserialize(reader).map(u64::from)
+}
+
+/// Deserializes "varint" from an async reader.
+#[cfg(feature = "tokio")]
+pub async fn deserialize_varint_async<R: AsyncRead + Unpin>(reader: &mut R) -> Result<u64, ParseError> {
+    VarInt::deserialize_async(reader).await.map(u64::from)
+}
+
+/// Deserializes "varint", rejecting encodings which aren't the shortest possible.
+///
+/// See `VarInt::deserialize_canonical`.
+#[cfg(feature = "std")]
+pub fn deserialize_varint_canonical<R: Read>(reader: &mut R) -> Result<u64, ParseError> {
+    VarInt::deserialize_canonical(reader).map(u64::from)
+}
+
+/// Serializes "varint" as defined by Bitcoin protocol.
+///
+/// Always picks the shortest encoding, so this is exact inverse of `deserialize_varint`.
+#[cfg(feature = "std")]
+pub fn serialize_varint<W: Write>(writer: &mut W, value: u64) -> io::Result<()> {
+    VarInt(value).serialize(writer)
+}
+
+/// Returns the number of bytes `serialize_varint` writes for the value.
+pub fn varint_len(value: u64) -> usize {
+    VarInt(value).serialized_len()
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::cursor::Cursor;
+
+    #[test]
+    fn boundaries() {
+        let cases: &[(u64, &[u8])] = &[
+            (0, &[0x00]),
+            (252, &[0xfc]),
+            (253, &[0xfd, 0xfd, 0x00]),
+            (0xFFFF, &[0xfd, 0xff, 0xff]),
+            (0x10000, &[0xfe, 0x00, 0x00, 0x01, 0x00]),
+            (0xFFFF_FFFF, &[0xfe, 0xff, 0xff, 0xff, 0xff]),
+            (0x1_0000_0000, &[0xff, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00]),
+            (u64::MAX, &[0xff; 9]),
+        ];
+
+        for &(value, bytes) in cases {
+            let mut serialized = Vec::new();
+            serialize_varint(&mut serialized, value).unwrap();
+            assert_eq!(serialized, bytes, "value {}", value);
+            assert_eq!(varint_len(value), bytes.len());
+            assert_eq!(deserialize_varint(&mut &*bytes).unwrap(), value);
+            assert_eq!(deserialize_varint_canonical(&mut &*bytes).unwrap(), value);
+
+            let mut data = bytes;
+            assert_eq!(data.read_varint().unwrap(), value);
+            assert!(data.is_empty());
+        }
+    }
+
+    #[test]
+    fn non_canonical() {
+        let cases: &[&[u8]] = &[
+            &[0xfd, 0xfc, 0x00],
+            &[0xfe, 0xff, 0xff, 0x00, 0x00],
+            &[0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00],
+        ];
+
+        for bytes in cases {
+            let value = deserialize_varint(&mut &**bytes).unwrap();
+            match deserialize_varint_canonical(&mut &**bytes) {
+                Err(ParseError::NonCanonicalVarint { value: rejected }) => assert_eq!(rejected, value),
+                result => panic!("unexpected result {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        for bytes in [&[][..], &[0xfd, 0x00], &[0xfe, 0x00, 0x00, 0x00], &[0xff, 0x00]] {
+            assert!(matches!(deserialize_varint(&mut &*bytes), Err(ParseError::UnexpectedEof)));
+            assert!(matches!((&mut &*bytes).read_varint(), Err(ParseError::UnexpectedEof)));
+        }
+    }
+}
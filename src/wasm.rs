@@ -0,0 +1,49 @@
+//! Entry points for JavaScript, exported using `wasm-bindgen`.
+
+use wasm_bindgen::prelude::*;
+use crate::transaction::Transaction;
+
+/// Parses the transaction from hex and returns it decoded as by `decoderawtransaction`.
+///
+/// The JSON is parsed by JavaScript, so the amounts become regular numbers.
+#[wasm_bindgen]
+pub fn parse_transaction_hex(hex: &str) -> Result<JsValue, JsError> {
+    let transaction = Transaction::from_hex(hex)?;
+    let json = transaction.to_decoded_json().to_string();
+
+    js_sys::JSON::parse(&json).map_err(|_| JsError::new("failed to parse JSON of the transaction"))
+}
+
+/// Parses the transaction from hex and returns its txid as displayed by Bitcoin Core.
+#[wasm_bindgen]
+pub fn transaction_txid(hex: &str) -> Result<String, JsError> {
+    Ok(Transaction::from_hex(hex)?.txid().to_reversed_hex())
+}
+
+// Run in Node.js using `cargo test --target wasm32-unknown-unknown --features wasm`
+// with `CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner`.
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+    use super::*;
+    use crate::test_vectors::*;
+
+    #[wasm_bindgen_test]
+    fn parse() {
+        let json = parse_transaction_hex(TX_170).unwrap();
+        let txid = js_sys::Reflect::get(&json, &JsValue::from_str("txid")).unwrap();
+        assert_eq!(txid.as_string().unwrap(), TX_170_TXID);
+
+        let outputs = js_sys::Reflect::get(&json, &JsValue::from_str("vout")).unwrap();
+        assert_eq!(js_sys::Array::from(&outputs).length(), 2);
+
+        assert!(parse_transaction_hex("0100").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn txid() {
+        assert_eq!(transaction_txid(TX_170).unwrap(), TX_170_TXID);
+        assert_eq!(transaction_txid(BIP143_P2WPKH).unwrap(), BIP143_P2WPKH_TXID);
+        assert!(transaction_txid("zz").is_err());
+    }
+}
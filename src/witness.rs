@@ -0,0 +1,250 @@
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::{Read, Write};
+#[cfg(feature = "std")]
+use crate::error::ParseError;
+#[cfg(feature = "std")]
+use crate::limits;
+use crate::prelude::*;
+#[cfg(feature = "std")]
+use crate::varint::{deserialize_varint, serialize_varint};
+use crate::varint::varint_len;
+#[cfg(feature = "tokio")]
+use crate::varint::deserialize_varint_async;
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Sanity check. Block can contain only 4M of bytes including witness
+/// and each item has at least one byte.
+pub(crate) const MAX_ITEMS: u64 = 4_000_000;
+/// Same reasoning as for `MAX_ITEMS`.
+pub(crate) const MAX_ITEM_LEN: u64 = 4_000_000;
+
+/// Witness stack of single transaction input. (BIP141)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Witness(pub Vec<Vec<u8>>);
+
+#[cfg(feature = "std")]
+impl Witness {
+    /// Deserializes the witness stack from the blockchain data
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Self, ParseError> {
+        let item_count = deserialize_varint(reader)?;
+        if item_count > MAX_ITEMS {
+            return Err(ParseError::TooManyWitnessItems { count: item_count });
+        }
+
+        let mut items = Vec::with_capacity(limits::preallocation(item_count));
+        for _ in 0..item_count {
+            let len = deserialize_varint(reader)?;
+            if len > MAX_ITEM_LEN {
+                return Err(ParseError::WitnessItemTooLong { len });
+            }
+
+            let mut reader = reader.by_ref().take(len);
+            let mut item = Vec::with_capacity(limits::preallocation(len));
+            io::copy(&mut reader, &mut item)?;
+            if item.len() as u64 != len {
+                return Err(ParseError::UnexpectedEof);
+            }
+            items.push(item);
+        }
+
+        Ok(Witness(items))
+    }
+
+    /// Serializes the witness stack the same way it's stored in the blockchain
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        serialize_varint(writer, self.0.len() as u64)?;
+        for item in &self.0 {
+            serialize_varint(writer, item.len() as u64)?;
+            writer.write_all(item)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Witness {
+    /// Deserializes the witness stack from an async reader
+    pub async fn deserialize_async<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self, ParseError> {
+        let item_count = deserialize_varint_async(reader).await?;
+        if item_count > MAX_ITEMS {
+            return Err(ParseError::TooManyWitnessItems { count: item_count });
+        }
+
+        let mut items = Vec::with_capacity(limits::preallocation(item_count));
+        for _ in 0..item_count {
+            let len = deserialize_varint_async(reader).await?;
+            if len > MAX_ITEM_LEN {
+                return Err(ParseError::WitnessItemTooLong { len });
+            }
+
+            let mut item = Vec::with_capacity(limits::preallocation(len));
+            reader.take(len).read_to_end(&mut item).await?;
+            if item.len() as u64 != len {
+                return Err(ParseError::UnexpectedEof);
+            }
+            items.push(item);
+        }
+
+        Ok(Witness(items))
+    }
+}
+
+impl Witness {
+    /// Returns the number of bytes `serialize` writes.
+    pub fn serialized_len(&self) -> usize {
+        let items_len = self.0.iter().map(|item| varint_len(item.len() as u64) + item.len()).sum::<usize>();
+        varint_len(self.0.len() as u64) + items_len
+    }
+
+    /// Splits the witness of Taproot (SegWit v1) input into its parts. (BIP341)
+    ///
+    /// The witness can't tell whether the spent output is Taproot, this must be
+    /// checked by the caller. Returns `None` if the stack is empty or the control
+    /// block has invalid length.
+    pub fn taproot_spend(&self) -> Option<TaprootSpend<'_>> {
+        let mut items = &self.0[..];
+        let annex = match items {
+            [.., last] if items.len() >= 2 && last.first() == Some(&TAPROOT_ANNEX_TAG) => {
+                items = &items[..items.len() - 1];
+                Some(&last[..])
+            },
+            _ => None,
+        };
+
+        match items {
+            [] => None,
+            [signature] => Some(TaprootSpend::KeyPath { signature, annex }),
+            [inputs @ .., script, control_block] => Some(TaprootSpend::ScriptPath {
+                inputs,
+                script,
+                control_block: ControlBlock::from_slice(control_block)?,
+                annex,
+            }),
+        }
+    }
+}
+
+/// First byte of the annex, distinguishing it from other witness items.
+const TAPROOT_ANNEX_TAG: u8 = 0x50;
+
+/// Parts of Taproot input witness, returned by `Witness::taproot_spend`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaprootSpend<'a> {
+    /// Spend using the output key.
+    KeyPath {
+        signature: &'a [u8],
+        /// Data reserved for future extensions, including the `0x50` tag
+        annex: Option<&'a [u8]>,
+    },
+    /// Spend revealing a script committed to by the output key.
+    ScriptPath {
+        /// Items the script is executed with
+        inputs: &'a [Vec<u8>],
+        script: &'a [u8],
+        control_block: ControlBlock<'a>,
+        /// Data reserved for future extensions, including the `0x50` tag
+        annex: Option<&'a [u8]>,
+    },
+}
+
+/// Proves that the script of script path spend is committed to by the output key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControlBlock<'a>(&'a [u8]);
+
+impl<'a> ControlBlock<'a> {
+    /// Maximum number of hashes in merkle path.
+    const MAX_PATH_LEN: usize = 128;
+
+    /// Checks the length of the control block, which must be 33 + 32 * m bytes.
+    pub fn from_slice(data: &'a [u8]) -> Option<Self> {
+        if data.len() < 33 || !(data.len() - 33).is_multiple_of(32) || (data.len() - 33) / 32 > Self::MAX_PATH_LEN {
+            return None;
+        }
+
+        Some(ControlBlock(data))
+    }
+
+    /// Returns version of the script leaf, `0xc0` for Tapscript (BIP342)
+    pub fn leaf_version(&self) -> u8 {
+        self.0[0] & 0xfe
+    }
+
+    /// Returns whether Y coordinate of the output key is odd
+    pub fn output_key_parity(&self) -> bool {
+        self.0[0] & 1 != 0
+    }
+
+    /// Returns the x-only internal key
+    pub fn internal_key(&self) -> &'a [u8] {
+        &self.0[1..33]
+    }
+
+    /// Returns iterator over hashes of the merkle path from the leaf to the root
+    pub fn merkle_path(&self) -> impl Iterator<Item = &'a [u8]> {
+        self.0[33..].chunks(32)
+    }
+
+    /// Returns the whole control block
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn taproot_key_path() {
+        let witness = Witness(vec![vec![0xaa; 64]]);
+        assert_eq!(witness.taproot_spend(), Some(TaprootSpend::KeyPath { signature: &[0xaa; 64], annex: None }));
+
+        let annex = vec![TAPROOT_ANNEX_TAG, 0x01];
+        let witness = Witness(vec![vec![0xaa; 65], annex.clone()]);
+        assert_eq!(witness.taproot_spend(), Some(TaprootSpend::KeyPath { signature: &[0xaa; 65], annex: Some(&annex) }));
+
+        // single item is never annex
+        let witness = Witness(vec![annex.clone()]);
+        assert_eq!(witness.taproot_spend(), Some(TaprootSpend::KeyPath { signature: &annex, annex: None }));
+
+        assert_eq!(Witness(Vec::new()).taproot_spend(), None);
+    }
+
+    #[test]
+    fn taproot_script_path() {
+        let mut control_block = vec![0xc1];
+        control_block.extend_from_slice(&[0x11; 32]);
+        control_block.extend_from_slice(&[0x22; 32]);
+        // <key> OP_CHECKSIG
+        let mut script = vec![0x20];
+        script.extend_from_slice(&[0x33; 32]);
+        script.push(0xac);
+        let mut witness = Witness(vec![vec![0xaa; 64], script.clone(), control_block.clone()]);
+
+        match witness.taproot_spend() {
+            Some(TaprootSpend::ScriptPath { inputs, script: spent_script, control_block: parsed, annex: None }) => {
+                assert_eq!(inputs, [vec![0xaa; 64]]);
+                assert_eq!(spent_script, &*script);
+                assert_eq!(parsed.as_bytes(), &*control_block);
+                assert_eq!(parsed.leaf_version(), 0xc0);
+                assert!(parsed.output_key_parity());
+                assert_eq!(parsed.internal_key(), [0x11; 32]);
+                assert_eq!(parsed.merkle_path().collect::<Vec<_>>(), [[0x22; 32]]);
+            },
+            spend => panic!("unexpected spend {:?}", spend),
+        }
+
+        witness.0.push(vec![TAPROOT_ANNEX_TAG]);
+        match witness.taproot_spend() {
+            Some(TaprootSpend::ScriptPath { annex, .. }) => assert_eq!(annex, Some(&[TAPROOT_ANNEX_TAG][..])),
+            spend => panic!("unexpected spend {:?}", spend),
+        }
+
+        control_block.push(0);
+        let witness = Witness(vec![script, control_block]);
+        assert_eq!(witness.taproot_spend(), None);
+    }
+}
@@ -0,0 +1,39 @@
+//! Checks that parsing from async readers gives the same results as the sync path.
+
+#![cfg(feature = "tokio")]
+
+extern crate btc_parse_tutor;
+extern crate tokio;
+
+use btc_parse_tutor::{ParseError, Transaction};
+
+/// Transaction from block 170, the first one spending coins.
+const LEGACY: &str = "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000";
+
+/// SegWit transaction from BIP143 test vectors.
+const SEGWIT: &str = "01000000000102fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f00000000494830450221008b9d1dc26ba6a9cb62127b02742fa9d754cd3bebf337f7a55d114c8e5cdd30be022040529b194ba3f9281a99f2b1c0a19c0489bc22ede944ccf4ecbab4cc618ef3ed01eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac000247304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb1366d7f01cc44a0220573a954c4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee0121025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeee635711000000";
+
+fn unhex(hex: &str) -> Vec<u8> {
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+}
+
+#[tokio::test]
+async fn same_as_sync() {
+    for hex in &[LEGACY, SEGWIT] {
+        let data = unhex(hex);
+        let sync = Transaction::deserialize(&mut &*data).unwrap();
+        let mut reader = &*data;
+        let async_parsed = Transaction::deserialize_async(&mut reader).await.unwrap();
+
+        assert_eq!(async_parsed, sync);
+        assert!(reader.is_empty());
+    }
+}
+
+#[tokio::test]
+async fn truncated() {
+    let data = unhex(SEGWIT);
+    let error = Transaction::deserialize_async(&mut &data[..data.len() - 1]).await.unwrap_err();
+
+    assert!(matches!(error.root_cause(), ParseError::UnexpectedEof));
+}